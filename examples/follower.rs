@@ -26,7 +26,13 @@ async fn bot(
     flag: String,
     target: String,
 ) -> Result<(), Box<Error + 'static>> {
-    let mut client = r#await!(Client::new_insecure(server))?;
+    // Use TLS for wss:// URLs and a plain connection
+    // otherwise (e.g. when running against a local
+    // test server).
+    let mut client = match server.scheme() {
+        "wss" => r#await!(Client::new_insecure(server))?,
+        _ => r#await!(Client::new(server))?,
+    };
 
     r#await!(client.send(client::Login {
         flag,
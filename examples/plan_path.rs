@@ -0,0 +1,102 @@
+//! Plan a path between two points on the default map
+//! and print the waypoints. Useful for checking what
+//! the navigation helpers will do without having to
+//! fly a bot there.
+
+extern crate airmash_client;
+extern crate clap;
+
+use airmash_client::map;
+use airmash_client::protocol::Position;
+
+use std::time::Instant;
+
+fn parse_pos(s: &str) -> Result<Position, String> {
+    let parts: Vec<_> = s.split(',').map(|x| x.trim().parse::<f32>()).collect();
+
+    match parts.as_slice() {
+        [Ok(x), Ok(y)] => Ok(Position::new(*x, *y)),
+        _ => Err(format!("Expected a position of the form x,y but got {}", s)),
+    }
+}
+
+fn print_path(name: &str, start: Position, path: Option<Vec<Position>>, time: Instant) {
+    let elapsed = time.elapsed();
+
+    let path = match path {
+        Some(path) => path,
+        None => {
+            println!("{}: no path found", name);
+            return;
+        }
+    };
+
+    let mut prev = start;
+    let mut length = 0.0;
+    for pos in path.iter() {
+        length += (*pos - prev).length().inner();
+        prev = *pos;
+    }
+
+    println!(
+        "{}: {} waypoints, length {:.0}, planned in {:?}",
+        name,
+        path.len(),
+        length,
+        elapsed
+    );
+    for pos in path {
+        println!("    {:.0}, {:.0}", pos.x.inner(), pos.y.inner());
+    }
+}
+
+fn main() {
+    use clap::*;
+
+    let args = App::new("Path Planner")
+        .about("Plans a path around the terrain of the default map")
+        .author("STEAMROLLER")
+        .arg(
+            Arg::with_name("start")
+                .help("The start position, as x,y")
+                .required(true)
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::with_name("goal")
+                .help("The goal position, as x,y")
+                .required(true)
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::with_name("grid")
+                .long("grid")
+                .help("Also plan a path over the terrain grid for comparison."),
+        )
+        .get_matches();
+
+    let (start, goal) = match (
+        parse_pos(args.value_of("start").unwrap()),
+        parse_pos(args.value_of("goal").unwrap()),
+    ) {
+        (Ok(start), Ok(goal)) => (start, goal),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    // Build the graph up front so that it isn't
+    // included in the planning time.
+    let time = Instant::now();
+    let nodes = map::GRAPH.nodes().count();
+    println!("Built visibility graph with {} nodes in {:?}", nodes, time.elapsed());
+
+    let time = Instant::now();
+    print_path("visibility", start, map::find_path(start, goal), time);
+
+    if args.is_present("grid") {
+        let time = Instant::now();
+        print_path("grid", start, map::find_grid_path(start, goal), time);
+    }
+}
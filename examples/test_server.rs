@@ -0,0 +1,54 @@
+//! Run the stand-in server locally so that bots
+//! (such as the follower example) can be tested
+//! without connecting to a real server.
+//!
+//! Run with `cargo run --example test_server --features test-server`.
+
+#![feature(futures_api, await_macro, async_await)]
+
+extern crate airmash_client;
+extern crate clap;
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+extern crate tokio;
+
+use airmash_client::test_server::{self, ServerConfig};
+
+use std::net::SocketAddr;
+
+use tokio::r#await;
+
+async fn run_server(addr: SocketAddr) {
+    if let Err(e) = r#await!(test_server::serve(addr, ServerConfig::default())) {
+        error!("The server shut down with an error: {}", e);
+    }
+}
+
+fn main() {
+    use clap::*;
+
+    env_logger::init();
+
+    let args = App::new("Test Server")
+        .about("A stand-in AIRMASH server for testing bots")
+        .author("STEAMROLLER")
+        .arg(
+            Arg::with_name("addr")
+                .long("addr")
+                .help("The address to listen on.")
+                .default_value("127.0.0.1:3501")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    let addr = match args.value_of("addr").unwrap().parse() {
+        Ok(addr) => addr,
+        Err(e) => {
+            eprintln!("An error occurred while parsing the address:\n{}", e);
+            return;
+        }
+    };
+
+    tokio::run_async(run_server(addr));
+}
@@ -0,0 +1,156 @@
+use crate::game::ClientKeyState;
+use crate::protocol::KeyCode;
+
+#[derive(Copy, Clone, Debug)]
+struct KeyRequest {
+    key: KeyCode,
+    state: bool,
+    priority: i32,
+}
+
+/// Decides which keys end up held when several
+/// actions want different things.
+///
+/// For each key the request with the highest priority
+/// wins. When priorities are equal the earliest request
+/// wins. Left/right and up/down are treated as pairs:
+/// if the winning request for one key of a pair presses
+/// it then the other key is released, so two actions
+/// can never end up holding both at once. Keys that
+/// nobody asks for are released.
+#[derive(Clone, Debug, Default)]
+pub struct KeyArbiter {
+    requests: Vec<KeyRequest>,
+}
+
+impl KeyArbiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn request(&mut self, key: KeyCode, state: bool, priority: i32) {
+        self.requests.push(KeyRequest {
+            key,
+            state,
+            priority,
+        });
+    }
+
+    /// Work out the final key state and clear all the
+    /// requests for the next tick.
+    pub fn resolve(&mut self) -> ClientKeyState {
+        use self::KeyCode::*;
+
+        let mut keys = ClientKeyState::default();
+
+        for &key in [Fire, Special].iter() {
+            if let Some(req) = self.winner(&[key]) {
+                keys.set(key, req.state);
+            }
+        }
+
+        for &(a, b) in [(Left, Right), (Up, Down)].iter() {
+            match self.winner(&[a, b]) {
+                Some(ref req) if req.state => keys.set(req.key, true),
+                Some(ref req) => {
+                    // The winner only wants its key released,
+                    // the other key is up to its own requests.
+                    let other = if req.key == a { b } else { a };
+
+                    if let Some(req) = self.winner(&[other]) {
+                        keys.set(other, req.state);
+                    }
+                }
+                None => (),
+            }
+        }
+
+        self.requests.clear();
+        keys
+    }
+
+    fn winner(&self, keys: &[KeyCode]) -> Option<KeyRequest> {
+        self.requests
+            .iter()
+            .filter(|req| keys.contains(&req.key))
+            // max_by_key returns the last maximum, so search
+            // in reverse to give ties to the earliest request.
+            .rev()
+            .max_by_key(|req| req.priority)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::KeyCode::*;
+
+    #[test]
+    fn highest_priority_wins() {
+        let mut arbiter = KeyArbiter::new();
+        arbiter.request(Fire, true, 0);
+        arbiter.request(Fire, false, 5);
+        assert!(!arbiter.resolve().fire);
+
+        arbiter.request(Fire, false, 0);
+        arbiter.request(Fire, true, 5);
+        assert!(arbiter.resolve().fire);
+    }
+
+    #[test]
+    fn earliest_wins_ties() {
+        let mut arbiter = KeyArbiter::new();
+        arbiter.request(Special, true, 1);
+        arbiter.request(Special, false, 1);
+        assert!(arbiter.resolve().special);
+
+        arbiter.request(Special, false, 1);
+        arbiter.request(Special, true, 1);
+        assert!(!arbiter.resolve().special);
+    }
+
+    #[test]
+    fn left_and_right_are_exclusive() {
+        let mut arbiter = KeyArbiter::new();
+        arbiter.request(Left, true, 1);
+        arbiter.request(Right, true, 2);
+
+        let keys = arbiter.resolve();
+        assert!(keys.right);
+        assert!(!keys.left);
+    }
+
+    #[test]
+    fn up_and_down_are_exclusive() {
+        let mut arbiter = KeyArbiter::new();
+        arbiter.request(Up, true, 0);
+        arbiter.request(Down, true, 0);
+
+        let keys = arbiter.resolve();
+        assert!(keys.up);
+        assert!(!keys.down);
+    }
+
+    #[test]
+    fn released_winner_leaves_other_key() {
+        let mut arbiter = KeyArbiter::new();
+        arbiter.request(Left, false, 5);
+        arbiter.request(Right, true, 1);
+
+        let keys = arbiter.resolve();
+        assert!(keys.right);
+        assert!(!keys.left);
+    }
+
+    #[test]
+    fn unrequested_keys_are_released() {
+        let mut arbiter = KeyArbiter::new();
+        arbiter.request(Up, true, 0);
+        arbiter.request(Fire, true, 0);
+        assert_ne!(arbiter.resolve(), ClientKeyState::default());
+
+        // Requests only last for a single tick
+        assert_eq!(arbiter.resolve(), ClientKeyState::default());
+    }
+}
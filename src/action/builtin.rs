@@ -0,0 +1,355 @@
+//! Actions for the things that the blocking helpers
+//! on `ImplClient` do.
+
+use std::time::Instant;
+
+use super::{Action, ActionContext, ActionStatus};
+use crate::client::{
+    angle_to, can_fire, evasion_keys, heading_error, lead_angle, turn_key, upgrade_settled,
+    UPGRADE_TIMEOUT,
+};
+use crate::game::UpgradeStrategy;
+use crate::protocol::*;
+use crate::ClientEvent;
+
+/// Within this distance `Follow` stops thrusting.
+const FOLLOW_DISTANCE: f32 = 200.0;
+/// Within this distance `RunStraightAt` is done.
+const RUN_ARRIVE_DISTANCE: f32 = 100.0;
+/// Within this distance `RunStraightAt` lets off the
+/// throttle while turning so that it doesn't end up
+/// circling around the target.
+const RUN_SLOW_DISTANCE: f32 = 500.0;
+/// How far off of the target our heading can be before
+/// `RunStraightAt` counts as turning.
+const RUN_TURN_TOLERANCE: f32 = 1.0;
+
+/// Turn to face a given heading.
+#[derive(Copy, Clone, Debug)]
+pub struct TurnTo {
+    pub target: Rotation,
+}
+
+impl TurnTo {
+    pub fn new(target: Rotation) -> Self {
+        Self { target }
+    }
+}
+
+impl Action for TurnTo {
+    fn name(&self) -> &str {
+        "turn-to"
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, _: &ClientEvent) -> ActionStatus {
+        match turn_key(ctx.world(), self.target) {
+            Some(key) => {
+                ctx.press(key);
+                ActionStatus::Running
+            }
+            None => ActionStatus::Done,
+        }
+    }
+}
+
+/// Turn to face a given position.
+#[derive(Copy, Clone, Debug)]
+pub struct PointAt {
+    pub pos: Position,
+}
+
+impl PointAt {
+    pub fn new(pos: Position) -> Self {
+        Self { pos }
+    }
+}
+
+impl Action for PointAt {
+    fn name(&self) -> &str {
+        "point-at"
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, _: &ClientEvent) -> ActionStatus {
+        let angle = angle_to(ctx.world().get_me().pos, self.pos);
+
+        match turn_key(ctx.world(), angle) {
+            Some(key) => {
+                ctx.press(key);
+                ActionStatus::Running
+            }
+            None => ActionStatus::Done,
+        }
+    }
+}
+
+/// Chase a player around until they leave.
+#[derive(Copy, Clone, Debug)]
+pub struct Follow {
+    pub player: u16,
+}
+
+impl Follow {
+    pub fn new(player: u16) -> Self {
+        Self { player }
+    }
+}
+
+impl Action for Follow {
+    fn name(&self) -> &str {
+        "follow"
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, _: &ClientEvent) -> ActionStatus {
+        let pos = match ctx.world().players.get(&self.player) {
+            Some(player) => player.pos,
+            None => return ActionStatus::Done,
+        };
+        let mypos = ctx.world().get_me().pos;
+
+        if let Some(key) = turn_key(ctx.world(), angle_to(mypos, pos)) {
+            ctx.press(key);
+        }
+        if (pos - mypos).length().inner() > FOLLOW_DISTANCE {
+            ctx.press(KeyCode::Up);
+        }
+
+        ActionStatus::Running
+    }
+}
+
+/// Fly straight towards a position without doing any
+/// pathfinding.
+#[derive(Copy, Clone, Debug)]
+pub struct RunStraightAt {
+    pub pos: Position,
+}
+
+impl RunStraightAt {
+    pub fn new(pos: Position) -> Self {
+        Self { pos }
+    }
+}
+
+impl Action for RunStraightAt {
+    fn name(&self) -> &str {
+        "run-straight-at"
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, _: &ClientEvent) -> ActionStatus {
+        let me = ctx.world().get_me();
+        let dist = (self.pos - me.pos).length().inner();
+
+        if dist < RUN_ARRIVE_DISTANCE {
+            return ActionStatus::Done;
+        }
+
+        let angle = angle_to(me.pos, self.pos);
+        let turning = heading_error(angle, me.rot).inner().abs() > RUN_TURN_TOLERANCE;
+
+        if let Some(key) = turn_key(ctx.world(), angle) {
+            ctx.press(key);
+        }
+        if !turning || dist >= RUN_SLOW_DISTANCE {
+            ctx.press(KeyCode::Up);
+        }
+
+        ActionStatus::Running
+    }
+}
+
+/// Fire a single shot, optionally turning to lead a
+/// target first.
+#[derive(Copy, Clone, Debug)]
+pub struct Fire {
+    pub target: Option<u16>,
+    fired: bool,
+}
+
+impl Fire {
+    /// Fire in whatever direction we are facing.
+    pub fn now() -> Self {
+        Self {
+            target: None,
+            fired: false,
+        }
+    }
+
+    /// Turn to lead the given player and fire at them.
+    pub fn at(player: u16) -> Self {
+        Self {
+            target: Some(player),
+            fired: false,
+        }
+    }
+}
+
+impl Action for Fire {
+    fn name(&self) -> &str {
+        "fire"
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, evt: &ClientEvent) -> ActionStatus {
+        // Events can arrive back to back, so letting go on
+        // the next one might release the key right after
+        // pressing it. Hold it until the next frame.
+        if self.fired {
+            if let ClientEvent::Frame(_) = evt {
+                return ActionStatus::Done;
+            }
+
+            ctx.press(KeyCode::Fire);
+            return ActionStatus::Running;
+        }
+
+        if let Some(target) = self.target {
+            let angle = match lead_angle(ctx.world(), target) {
+                Some(angle) => angle,
+                None => return ActionStatus::Failed,
+            };
+
+            if let Some(key) = turn_key(ctx.world(), angle) {
+                ctx.press(key);
+                return ActionStatus::Running;
+            }
+        }
+
+        if can_fire(ctx.world()) {
+            ctx.press(KeyCode::Fire);
+            self.fired = true;
+        }
+
+        ActionStatus::Running
+    }
+}
+
+/// Send a chat message.
+#[derive(Clone, Debug)]
+pub struct SendChat {
+    packet: Option<ClientPacket>,
+}
+
+impl SendChat {
+    pub fn public(text: String) -> Self {
+        Self::from_packet(client::Chat { text })
+    }
+
+    pub fn team(text: String) -> Self {
+        Self::from_packet(client::TeamChat { text })
+    }
+
+    pub fn say(text: String) -> Self {
+        Self::from_packet(client::Say { text })
+    }
+
+    pub fn whisper(player: u16, text: String) -> Self {
+        Self::from_packet(client::Whisper {
+            id: player.into(),
+            text,
+        })
+    }
+
+    fn from_packet<P: Into<ClientPacket>>(packet: P) -> Self {
+        Self {
+            packet: Some(packet.into()),
+        }
+    }
+}
+
+impl Action for SendChat {
+    fn name(&self) -> &str {
+        "chat"
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, _: &ClientEvent) -> ActionStatus {
+        if let Some(packet) = self.packet.take() {
+            ctx.send(packet);
+        }
+
+        ActionStatus::Done
+    }
+}
+
+/// Dodge incoming missiles. This never finishes so it
+/// should usually be added with a high priority and
+/// left running alongside everything else.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Evade;
+
+impl Action for Evade {
+    fn name(&self) -> &str {
+        "evade"
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, _: &ClientEvent) -> ActionStatus {
+        use self::KeyCode::*;
+
+        // Only step in when there's actually something
+        // to dodge, otherwise leave the keys to others.
+        if let Some(keys) = evasion_keys(ctx.world()) {
+            for &key in [Up, Down, Left, Right, Special].iter() {
+                ctx.set_key(key, keys.get(key));
+            }
+        }
+
+        ActionStatus::Running
+    }
+}
+
+/// Spend upgrade points as they come in, using a
+/// strategy to pick what to buy. This never finishes.
+///
+/// This is for bots that are driven by an [`ActionSet`].
+/// Other bots can use [`ImplClient::set_upgrade_strategy`]
+/// instead.
+///
+/// [`ActionSet`]: crate::action::ActionSet
+/// [`ImplClient::set_upgrade_strategy`]: crate::ImplClient::set_upgrade_strategy
+///
+/// Upgrades are bought one at a time, waiting for the
+/// server to confirm each one so that the strategy
+/// always sees how many points are left.
+#[derive(Copy, Clone, Debug)]
+pub struct SpendUpgrades {
+    pub strategy: UpgradeStrategy,
+    /// The upgrade that we're waiting on and when to give
+    /// up on it.
+    pending: Option<(UpgradeType, Instant)>,
+}
+
+impl SpendUpgrades {
+    pub fn new(strategy: UpgradeStrategy) -> Self {
+        Self {
+            strategy,
+            pending: None,
+        }
+    }
+}
+
+impl Action for SpendUpgrades {
+    fn name(&self) -> &str {
+        "spend-upgrades"
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, evt: &ClientEvent) -> ActionStatus {
+        if let Some(pending) = self.pending {
+            if !upgrade_settled(pending, evt) {
+                return ActionStatus::Running;
+            }
+
+            self.pending = None;
+        }
+
+        let ty = match self.strategy.next_upgrade(ctx.world()) {
+            Some(ty) if ctx.world().can_afford_upgrade(ty) => ty,
+            _ => return ActionStatus::Running,
+        };
+
+        ctx.send(client::Command {
+            com: "upgrade".to_owned(),
+            data: (ty as u8).to_string(),
+        });
+        self.pending = Some((ty, Instant::now() + UPGRADE_TIMEOUT));
+
+        ActionStatus::Running
+    }
+}
@@ -0,0 +1,108 @@
+//! Non-blocking actions.
+//!
+//! Helpers such as `follow` and `turn_to` take over the
+//! event loop until they are done. Actions are instead
+//! ticked once for every [`ClientEvent`] and only say
+//! which keys they want held and which packets should be
+//! sent, so any number of them can run at the same time.
+//! Conflicting key requests are settled by a
+//! [`KeyArbiter`] based on each action's priority.
+//!
+//! The built-in actions (`Fire`, `Follow`, ...) are only
+//! exported from this module so that they don't end up
+//! next to the keys and helpers of the same name.
+//!
+//! [`ClientEvent`]: crate::ClientEvent
+
+mod arbiter;
+mod builtin;
+mod set;
+
+pub use self::arbiter::KeyArbiter;
+pub use self::builtin::{
+    Evade, Fire, Follow, PointAt, RunStraightAt, SendChat, SpendUpgrades, TurnTo,
+};
+pub use self::set::{ActionId, ActionOutput, ActionSet};
+
+use crate::game::World;
+use crate::protocol::{ClientPacket, KeyCode};
+use crate::ClientEvent;
+
+/// The state of an action after it has been ticked.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ActionStatus {
+    /// The action wants to keep being ticked.
+    Running,
+    /// The action finished successfully.
+    Done,
+    /// The action can't be completed, e.g. because its
+    /// target left the game.
+    Failed,
+    /// The action was cancelled before it finished.
+    Cancelled,
+}
+
+/// A task that runs a little bit every time that the
+/// client gets an event.
+pub trait Action: Send {
+    /// A short name for logging and debugging.
+    fn name(&self) -> &str;
+
+    /// Do whatever needs to be done in response to an
+    /// event. Keys requested here only stay held as long
+    /// as they keep being requested on every tick.
+    fn tick(&mut self, ctx: &mut ActionContext, evt: &ClientEvent) -> ActionStatus;
+
+    /// Called instead of `tick` when the action has been
+    /// cancelled. Keys are released automatically, so
+    /// most actions don't need to do anything here.
+    fn cancel(&mut self, _ctx: &mut ActionContext) {}
+}
+
+/// What an action gets to work with while it's ticked.
+pub struct ActionContext<'a> {
+    world: &'a World,
+    priority: i32,
+    arbiter: &'a mut KeyArbiter,
+    packets: &'a mut Vec<ClientPacket>,
+}
+
+impl<'a> ActionContext<'a> {
+    pub(crate) fn new(
+        world: &'a World,
+        priority: i32,
+        arbiter: &'a mut KeyArbiter,
+        packets: &'a mut Vec<ClientPacket>,
+    ) -> Self {
+        Self {
+            world,
+            priority,
+            arbiter,
+            packets,
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        self.world
+    }
+
+    /// Ask for a key to be pressed or released for
+    /// this tick.
+    pub fn set_key(&mut self, key: KeyCode, state: bool) {
+        self.arbiter.request(key, state, self.priority);
+    }
+
+    pub fn press(&mut self, key: KeyCode) {
+        self.set_key(key, true);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.set_key(key, false);
+    }
+
+    /// Queue a packet to be sent once all the actions
+    /// have been ticked.
+    pub fn send<P: Into<ClientPacket>>(&mut self, packet: P) {
+        self.packets.push(packet.into());
+    }
+}
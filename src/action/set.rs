@@ -0,0 +1,310 @@
+use super::{Action, ActionContext, ActionStatus, KeyArbiter};
+use crate::game::{ClientKeyState, World};
+use crate::protocol::{ClientPacket, KeyCode};
+use crate::{Client, ClientEvent, ClientResult, ImplClient};
+
+/// Identifies an action within an [`ActionSet`].
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ActionId(u64);
+
+struct Entry {
+    id: ActionId,
+    priority: i32,
+    cancelled: bool,
+    action: Box<dyn Action>,
+}
+
+/// The result of ticking an [`ActionSet`].
+#[derive(Debug, Default)]
+pub struct ActionOutput {
+    /// The keys that should now be held.
+    pub keys: ClientKeyState,
+    /// Packets that the actions want sent.
+    pub packets: Vec<ClientPacket>,
+    /// Every action that finished during this tick.
+    pub finished: Vec<(ActionId, ActionStatus)>,
+}
+
+/// A group of actions that run together.
+///
+/// The set takes control of all the keys: any key that
+/// no running action asks for is released. Actions are
+/// ticked in the order that they were added.
+#[derive(Default)]
+pub struct ActionSet {
+    entries: Vec<Entry>,
+    arbiter: KeyArbiter,
+    next_id: u64,
+}
+
+impl ActionSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start running an action. Actions with a higher
+    /// priority win when they ask for conflicting keys.
+    pub fn add<A: Action + 'static>(&mut self, priority: i32, action: A) -> ActionId {
+        let id = ActionId(self.next_id);
+        self.next_id += 1;
+
+        self.entries.push(Entry {
+            id,
+            priority,
+            cancelled: false,
+            action: Box::new(action),
+        });
+
+        id
+    }
+
+    /// Cancel an action. It will be removed on the next
+    /// tick. Returns `false` if there is no such action.
+    pub fn cancel(&mut self, id: ActionId) -> bool {
+        match self.entries.iter_mut().find(|e| e.id == id) {
+            Some(entry) => {
+                entry.cancelled = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn cancel_all(&mut self) {
+        for entry in self.entries.iter_mut() {
+            entry.cancelled = true;
+        }
+    }
+
+    pub fn contains(&self, id: ActionId) -> bool {
+        self.entries.iter().any(|e| e.id == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The names of all the running actions.
+    pub fn names(&self) -> Vec<&str> {
+        self.entries.iter().map(|e| e.action.name()).collect()
+    }
+
+    /// Tick every action once.
+    ///
+    /// Until we have logged in there is no plane to
+    /// control so nothing is ticked and the current keys
+    /// are left as they are.
+    pub fn tick(&mut self, world: &World, evt: &ClientEvent) -> ActionOutput {
+        let mut output = ActionOutput::default();
+
+        if !world.players.contains_key(&world.me.id) {
+            output.keys = world.keys;
+            return output;
+        }
+
+        let mut i = 0;
+        while i < self.entries.len() {
+            let entry = &mut self.entries[i];
+            let mut ctx = ActionContext::new(
+                world,
+                entry.priority,
+                &mut self.arbiter,
+                &mut output.packets,
+            );
+
+            let status = if entry.cancelled {
+                entry.action.cancel(&mut ctx);
+                ActionStatus::Cancelled
+            } else {
+                entry.action.tick(&mut ctx, evt)
+            };
+
+            if status == ActionStatus::Running {
+                i += 1;
+            } else {
+                output.finished.push((entry.id, status));
+                self.entries.remove(i);
+            }
+        }
+
+        output.keys = self.arbiter.resolve();
+        output
+    }
+}
+
+impl<T: Client> ImplClient<T> {
+    /// Tick a set of actions with an event and send any
+    /// key changes and packets that they produce.
+    ///
+    /// This doesn't wait for any events itself, so a bot
+    /// can call it from its own event loop while also
+    /// reacting to other events. Returns the actions that
+    /// finished.
+    pub async fn tick_actions(
+        &mut self,
+        actions: &mut ActionSet,
+        evt: &ClientEvent,
+    ) -> ClientResult<Vec<(ActionId, ActionStatus)>> {
+        use self::KeyCode::*;
+
+        let output = actions.tick(self.world(), evt);
+
+        for &key in [Up, Down, Left, Right, Fire, Special].iter() {
+            let state = output.keys.get(key);
+
+            if self.world().keys.get(key) != state {
+                r#await!(self.send_key(key, state))?;
+            }
+        }
+
+        for packet in output.packets {
+            r#await!(self.send(packet))?;
+        }
+
+        Ok(output.finished)
+    }
+
+    /// Process events and tick the actions until all of
+    /// them have finished or the connection closes.
+    pub async fn run_actions(&mut self, actions: &mut ActionSet) -> ClientResult<()> {
+        while !actions.is_empty() {
+            let evt = match r#await!(self.next())? {
+                Some(evt) => evt,
+                None => break,
+            };
+
+            r#await!(self.tick_actions(actions, &evt))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Player;
+
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    /// Holds a key for a number of ticks and then finishes.
+    struct Hold {
+        key: KeyCode,
+        ticks: u32,
+        cancelled: Arc<AtomicBool>,
+    }
+
+    impl Hold {
+        fn new(key: KeyCode, ticks: u32) -> Self {
+            Self {
+                key,
+                ticks,
+                cancelled: Arc::new(AtomicBool::new(false)),
+            }
+        }
+    }
+
+    impl Action for Hold {
+        fn name(&self) -> &str {
+            "hold"
+        }
+
+        fn tick(&mut self, ctx: &mut ActionContext, _: &ClientEvent) -> ActionStatus {
+            if self.ticks == 0 {
+                return ActionStatus::Done;
+            }
+
+            self.ticks -= 1;
+            ctx.set_key(self.key, true);
+            ActionStatus::Running
+        }
+
+        fn cancel(&mut self, _: &mut ActionContext) {
+            self.cancelled.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn logged_in() -> World {
+        let mut world = World::default();
+        world.players.insert(world.me.id, Player::default());
+        world
+    }
+
+    fn frame() -> ClientEvent {
+        ClientEvent::Frame(Instant::now())
+    }
+
+    #[test]
+    fn nothing_ticks_before_login() {
+        let mut world = World::default();
+        world.keys.up = true;
+
+        let mut actions = ActionSet::new();
+        actions.add(0, Hold::new(KeyCode::Left, 0));
+
+        let output = actions.tick(&world, &frame());
+        assert_eq!(output.keys, world.keys);
+        assert!(output.finished.is_empty());
+        assert_eq!(actions.len(), 1);
+    }
+
+    #[test]
+    fn higher_priority_action_wins() {
+        let world = logged_in();
+        let mut actions = ActionSet::new();
+        actions.add(0, Hold::new(KeyCode::Left, 10));
+        actions.add(1, Hold::new(KeyCode::Right, 10));
+        actions.add(0, Hold::new(KeyCode::Up, 10));
+
+        let keys = actions.tick(&world, &frame()).keys;
+        assert!(keys.right);
+        assert!(!keys.left);
+        assert!(keys.up);
+    }
+
+    #[test]
+    fn finished_actions_release_keys() {
+        let world = logged_in();
+        let mut actions = ActionSet::new();
+        let id = actions.add(0, Hold::new(KeyCode::Up, 1));
+
+        let output = actions.tick(&world, &frame());
+        assert!(output.keys.up);
+        assert!(output.finished.is_empty());
+
+        let output = actions.tick(&world, &frame());
+        assert!(!output.keys.up);
+        assert_eq!(output.finished, vec![(id, ActionStatus::Done)]);
+        assert!(actions.is_empty());
+    }
+
+    #[test]
+    fn cancelled_actions_are_removed() {
+        let world = logged_in();
+        let mut actions = ActionSet::new();
+        let hold = Hold::new(KeyCode::Fire, 10);
+        let cancelled = hold.cancelled.clone();
+        let id = actions.add(0, hold);
+        let other = actions.add(0, Hold::new(KeyCode::Up, 10));
+
+        assert!(actions.tick(&world, &frame()).keys.fire);
+        assert!(actions.cancel(id));
+
+        let output = actions.tick(&world, &frame());
+        assert!(cancelled.load(Ordering::SeqCst));
+        assert!(!output.keys.fire);
+        assert!(output.keys.up);
+        assert_eq!(output.finished, vec![(id, ActionStatus::Cancelled)]);
+        assert!(!actions.contains(id));
+        assert!(actions.contains(other));
+
+        // Already gone
+        assert!(!actions.cancel(id));
+    }
+}
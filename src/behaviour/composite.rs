@@ -0,0 +1,435 @@
+use super::Node;
+use crate::action::{ActionContext, ActionStatus};
+use crate::ClientEvent;
+
+/// Runs its children one after another. Fails as soon
+/// as any child fails and succeeds once all of them
+/// have succeeded.
+pub struct Sequence {
+    name: String,
+    children: Vec<Box<dyn Node>>,
+    current: usize,
+}
+
+impl Sequence {
+    pub fn new(children: Vec<Box<dyn Node>>) -> Self {
+        Self {
+            name: "sequence".to_owned(),
+            children,
+            current: 0,
+        }
+    }
+
+    pub fn named<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+}
+
+impl Node for Sequence {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, evt: &ClientEvent) -> ActionStatus {
+        while let Some(child) = self.children.get_mut(self.current) {
+            match child.tick(ctx, evt) {
+                ActionStatus::Done => self.current += 1,
+                ActionStatus::Running => return ActionStatus::Running,
+                status => {
+                    self.current = 0;
+                    return status;
+                }
+            }
+        }
+
+        self.current = 0;
+        ActionStatus::Done
+    }
+
+    fn cancel(&mut self, ctx: &mut ActionContext) {
+        if let Some(child) = self.children.get_mut(self.current) {
+            child.cancel(ctx);
+        }
+        self.current = 0;
+    }
+
+    fn active_path(&self, path: &mut Vec<String>) {
+        path.push(self.name.clone());
+
+        if let Some(child) = self.children.get(self.current) {
+            child.active_path(path);
+        }
+    }
+}
+
+/// Picks the first child that doesn't fail.
+///
+/// Children are checked from the start every tick, so
+/// if an earlier child stops failing (e.g. because its
+/// condition became true) then the running child is
+/// cancelled in favour of it.
+pub struct Selector {
+    name: String,
+    children: Vec<Box<dyn Node>>,
+    running: Option<usize>,
+}
+
+impl Selector {
+    pub fn new(children: Vec<Box<dyn Node>>) -> Self {
+        Self {
+            name: "selector".to_owned(),
+            children,
+            running: None,
+        }
+    }
+
+    pub fn named<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+}
+
+impl Node for Selector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, evt: &ClientEvent) -> ActionStatus {
+        for i in 0..self.children.len() {
+            let status = self.children[i].tick(ctx, evt);
+
+            if status == ActionStatus::Failed {
+                continue;
+            }
+
+            if let Some(prev) = self.running {
+                if prev != i {
+                    self.children[prev].cancel(ctx);
+                }
+            }
+
+            self.running = match status {
+                ActionStatus::Running => Some(i),
+                _ => None,
+            };
+
+            return status;
+        }
+
+        if let Some(prev) = self.running.take() {
+            self.children[prev].cancel(ctx);
+        }
+
+        ActionStatus::Failed
+    }
+
+    fn cancel(&mut self, ctx: &mut ActionContext) {
+        if let Some(prev) = self.running.take() {
+            self.children[prev].cancel(ctx);
+        }
+    }
+
+    fn active_path(&self, path: &mut Vec<String>) {
+        path.push(self.name.clone());
+
+        if let Some(i) = self.running {
+            self.children[i].active_path(path);
+        }
+    }
+}
+
+/// When a [`Parallel`] node finishes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParallelPolicy {
+    /// Succeed once every child has succeeded and fail
+    /// as soon as one fails.
+    All,
+    /// Succeed as soon as one child succeeds and fail
+    /// once every child has failed.
+    Any,
+}
+
+/// Runs all of its children at the same time.
+///
+/// When the node finishes any children that are still
+/// running are cancelled.
+pub struct Parallel {
+    name: String,
+    policy: ParallelPolicy,
+    children: Vec<Box<dyn Node>>,
+    finished: Vec<Option<ActionStatus>>,
+}
+
+impl Parallel {
+    pub fn new(policy: ParallelPolicy, children: Vec<Box<dyn Node>>) -> Self {
+        Self {
+            name: "parallel".to_owned(),
+            policy,
+            finished: vec![None; children.len()],
+            children,
+        }
+    }
+
+    pub fn named<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    fn finish(&mut self, ctx: &mut ActionContext, status: ActionStatus) -> ActionStatus {
+        for (child, finished) in self.children.iter_mut().zip(self.finished.iter_mut()) {
+            if finished.is_none() {
+                child.cancel(ctx);
+            }
+            *finished = None;
+        }
+
+        status
+    }
+}
+
+impl Node for Parallel {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, evt: &ClientEvent) -> ActionStatus {
+        for (child, finished) in self.children.iter_mut().zip(self.finished.iter_mut()) {
+            if finished.is_none() {
+                match child.tick(ctx, evt) {
+                    ActionStatus::Running => (),
+                    status => *finished = Some(status),
+                }
+            }
+        }
+
+        let count = |status| self.finished.iter().filter(|&&s| s == Some(status)).count();
+        let (done, failed) = (count(ActionStatus::Done), count(ActionStatus::Failed));
+        let total = self.children.len();
+
+        let result = match self.policy {
+            ParallelPolicy::All if failed > 0 => ActionStatus::Failed,
+            ParallelPolicy::All if done == total => ActionStatus::Done,
+            ParallelPolicy::Any if done > 0 => ActionStatus::Done,
+            ParallelPolicy::Any if failed == total => ActionStatus::Failed,
+            _ => ActionStatus::Running,
+        };
+
+        match result {
+            ActionStatus::Running => result,
+            _ => self.finish(ctx, result),
+        }
+    }
+
+    fn cancel(&mut self, ctx: &mut ActionContext) {
+        self.finish(ctx, ActionStatus::Cancelled);
+    }
+
+    fn active_path(&self, path: &mut Vec<String>) {
+        path.push(self.name.clone());
+
+        let running: Vec<_> = self
+            .children
+            .iter()
+            .zip(self.finished.iter())
+            .filter(|(_, finished)| finished.is_none())
+            .map(|(child, _)| {
+                let mut branch = vec![];
+                child.active_path(&mut branch);
+                branch.join(" > ")
+            })
+            .collect();
+
+        if !running.is_empty() {
+            path.push(format!("[{}]", running.join(" | ")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::stub::{path, Harness, Log, Stub};
+    use super::*;
+    use crate::action::ActionStatus::*;
+
+    fn stub(name: &str, statuses: &[ActionStatus], log: &Log) -> Box<dyn Node> {
+        Stub::new(name, statuses, log).boxed()
+    }
+
+    #[test]
+    fn sequence_runs_children_in_order() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut seq = Sequence::new(vec![
+            stub("a", &[Done], &log),
+            stub("b", &[Running, Done], &log),
+        ]);
+
+        assert_eq!(h.tick(&mut seq), Running);
+        assert_eq!(log.take(), ["a", "b"]);
+        assert_eq!(path(&seq), ["sequence", "b"]);
+
+        // Finished children aren't ticked again
+        assert_eq!(h.tick(&mut seq), Done);
+        assert_eq!(log.take(), ["b"]);
+
+        // And then it starts over
+        assert_eq!(h.tick(&mut seq), Done);
+        assert_eq!(log.take(), ["a", "b"]);
+    }
+
+    #[test]
+    fn sequence_stops_at_failure() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut seq = Sequence::new(vec![
+            stub("a", &[Done], &log),
+            stub("b", &[Failed], &log),
+            stub("c", &[Done], &log),
+        ]);
+
+        assert_eq!(h.tick(&mut seq), Failed);
+        assert_eq!(log.take(), ["a", "b"]);
+        assert_eq!(h.tick(&mut seq), Failed);
+        assert_eq!(log.take(), ["a", "b"]);
+    }
+
+    #[test]
+    fn sequence_cancels_current_child() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut seq = Sequence::new(vec![stub("a", &[Done], &log), stub("b", &[Running], &log)]);
+
+        h.tick(&mut seq);
+        log.take();
+        h.cancel(&mut seq);
+        assert_eq!(log.take(), ["b cancelled"]);
+
+        // Cancelling resets it to the first child
+        h.tick(&mut seq);
+        assert_eq!(log.take(), ["a", "b"]);
+    }
+
+    #[test]
+    fn selector_picks_first_that_doesnt_fail() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut sel = Selector::new(vec![
+            stub("a", &[Failed], &log),
+            stub("b", &[Running], &log),
+            stub("c", &[Running], &log),
+        ]);
+
+        assert_eq!(h.tick(&mut sel), Running);
+        assert_eq!(log.take(), ["a", "b"]);
+        assert_eq!(path(&sel), ["selector", "b"]);
+    }
+
+    #[test]
+    fn selector_switches_to_earlier_child() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut sel = Selector::new(vec![
+            stub("a", &[Failed, Running], &log),
+            stub("b", &[Running], &log),
+        ]);
+
+        h.tick(&mut sel);
+        log.take();
+
+        assert_eq!(h.tick(&mut sel), Running);
+        assert_eq!(log.take(), ["a", "b cancelled"]);
+        assert_eq!(path(&sel), ["selector", "a"]);
+    }
+
+    #[test]
+    fn selector_fails_when_all_fail() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut sel = Selector::new(vec![stub("a", &[Failed], &log), stub("b", &[Failed], &log)]);
+
+        assert_eq!(h.tick(&mut sel), Failed);
+        assert_eq!(log.take(), ["a", "b"]);
+        assert_eq!(path(&sel), ["selector"]);
+    }
+
+    #[test]
+    fn selector_cancels_running_child() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut sel = Selector::new(vec![stub("a", &[Running], &log)]);
+
+        h.tick(&mut sel);
+        log.take();
+        h.cancel(&mut sel);
+        assert_eq!(log.take(), ["a cancelled"]);
+        assert_eq!(path(&sel), ["selector"]);
+    }
+
+    #[test]
+    fn parallel_all_waits_for_every_child() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut par = Parallel::new(
+            ParallelPolicy::All,
+            vec![stub("a", &[Done], &log), stub("b", &[Running, Done], &log)],
+        );
+
+        assert_eq!(h.tick(&mut par), Running);
+        assert_eq!(log.take(), ["a", "b"]);
+        assert_eq!(path(&par), ["parallel", "[b]"]);
+
+        assert_eq!(h.tick(&mut par), Done);
+        assert_eq!(log.take(), ["b"]);
+    }
+
+    #[test]
+    fn parallel_all_fails_on_first_failure() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut par = Parallel::new(
+            ParallelPolicy::All,
+            vec![stub("a", &[Running], &log), stub("b", &[Failed], &log)],
+        );
+
+        assert_eq!(h.tick(&mut par), Failed);
+        assert_eq!(log.take(), ["a", "b", "a cancelled"]);
+    }
+
+    #[test]
+    fn parallel_any_succeeds_on_first_success() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut par = Parallel::new(
+            ParallelPolicy::Any,
+            vec![
+                stub("a", &[Running], &log),
+                stub("b", &[Running, Done], &log),
+            ],
+        );
+
+        assert_eq!(h.tick(&mut par), Running);
+        assert_eq!(path(&par), ["parallel", "[a | b]"]);
+        log.take();
+
+        assert_eq!(h.tick(&mut par), Done);
+        assert_eq!(log.take(), ["a", "b", "a cancelled"]);
+    }
+
+    #[test]
+    fn parallel_any_fails_once_all_fail() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut par = Parallel::new(
+            ParallelPolicy::Any,
+            vec![
+                stub("a", &[Failed], &log),
+                stub("b", &[Running, Failed], &log),
+            ],
+        );
+
+        assert_eq!(h.tick(&mut par), Running);
+        assert_eq!(h.tick(&mut par), Failed);
+        assert_eq!(log.take(), ["a", "b", "b"]);
+    }
+}
@@ -0,0 +1,221 @@
+use std::time::{Duration, Instant};
+
+use super::Node;
+use crate::action::{ActionContext, ActionStatus};
+use crate::game::World;
+use crate::ClientEvent;
+
+/// How a [`Decorator`] changes its child.
+pub enum DecoratorKind {
+    /// Swap success and failure.
+    Invert,
+    /// Restart the child every time that it succeeds.
+    /// Stops and fails if the child fails.
+    Repeat,
+    /// Keep retrying the child until it succeeds.
+    Retry,
+    /// Fail if the child is still running after the
+    /// given duration.
+    Timeout(Duration),
+    /// Only run the child while the condition holds. The
+    /// condition is checked on every tick and the child
+    /// is cancelled as soon as it becomes false.
+    Guard(Box<dyn Fn(&World) -> bool + Send>),
+}
+
+/// A node that wraps a single child and changes how
+/// it behaves.
+pub struct Decorator {
+    name: String,
+    kind: DecoratorKind,
+    child: Box<dyn Node>,
+    running: bool,
+    started: Option<Instant>,
+}
+
+impl Decorator {
+    pub fn new<N: Node + 'static>(kind: DecoratorKind, child: N) -> Self {
+        let name = match kind {
+            DecoratorKind::Invert => "invert",
+            DecoratorKind::Repeat => "repeat",
+            DecoratorKind::Retry => "retry",
+            DecoratorKind::Timeout(_) => "timeout",
+            DecoratorKind::Guard(_) => "guard",
+        };
+
+        Self {
+            name: name.to_owned(),
+            kind,
+            child: Box::new(child),
+            running: false,
+            started: None,
+        }
+    }
+
+    pub fn invert<N: Node + 'static>(child: N) -> Self {
+        Self::new(DecoratorKind::Invert, child)
+    }
+
+    pub fn repeat<N: Node + 'static>(child: N) -> Self {
+        Self::new(DecoratorKind::Repeat, child)
+    }
+
+    pub fn retry<N: Node + 'static>(child: N) -> Self {
+        Self::new(DecoratorKind::Retry, child)
+    }
+
+    pub fn timeout<N: Node + 'static>(timeout: Duration, child: N) -> Self {
+        Self::new(DecoratorKind::Timeout(timeout), child)
+    }
+
+    pub fn guard<F, N>(cond: F, child: N) -> Self
+    where
+        F: Fn(&World) -> bool + Send + 'static,
+        N: Node + 'static,
+    {
+        Self::new(DecoratorKind::Guard(Box::new(cond)), child)
+    }
+
+    pub fn named<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    fn stop(&mut self, ctx: &mut ActionContext) {
+        if self.running {
+            self.child.cancel(ctx);
+        }
+        self.running = false;
+        self.started = None;
+    }
+}
+
+impl Node for Decorator {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, evt: &ClientEvent) -> ActionStatus {
+        use self::ActionStatus::*;
+
+        match self.kind {
+            DecoratorKind::Guard(ref cond) if !cond(ctx.world()) => {
+                self.stop(ctx);
+                return Failed;
+            }
+            DecoratorKind::Timeout(timeout) => {
+                let started = *self.started.get_or_insert_with(Instant::now);
+
+                if started.elapsed() > timeout {
+                    self.stop(ctx);
+                    return Failed;
+                }
+            }
+            _ => (),
+        }
+
+        let status = self.child.tick(ctx, evt);
+        self.running = status == Running;
+
+        let result = match (&self.kind, status) {
+            (DecoratorKind::Invert, Done) => Failed,
+            (DecoratorKind::Invert, Failed) => Done,
+            (DecoratorKind::Repeat, Done) => Running,
+            (DecoratorKind::Retry, Failed) => Running,
+            (_, status) => status,
+        };
+
+        if result != Running {
+            self.started = None;
+        }
+
+        result
+    }
+
+    fn cancel(&mut self, ctx: &mut ActionContext) {
+        self.stop(ctx);
+    }
+
+    fn active_path(&self, path: &mut Vec<String>) {
+        path.push(self.name.clone());
+
+        if self.running {
+            self.child.active_path(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::stub::{path, Harness, Log, Stub};
+    use super::*;
+    use crate::action::ActionStatus::*;
+
+    #[test]
+    fn invert_swaps_results() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut node = Decorator::invert(Stub::new("a", &[Done, Failed, Running], &log));
+
+        assert_eq!(h.tick(&mut node), Failed);
+        assert_eq!(h.tick(&mut node), Done);
+        assert_eq!(h.tick(&mut node), Running);
+        assert_eq!(path(&node), ["invert", "a"]);
+    }
+
+    #[test]
+    fn repeat_until_failure() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut node = Decorator::repeat(Stub::new("a", &[Done, Done, Failed], &log));
+
+        assert_eq!(h.tick(&mut node), Running);
+        assert_eq!(h.tick(&mut node), Running);
+        assert_eq!(h.tick(&mut node), Failed);
+        assert_eq!(log.take(), ["a", "a", "a"]);
+    }
+
+    #[test]
+    fn retry_until_success() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut node = Decorator::retry(Stub::new("a", &[Failed, Failed, Done], &log));
+
+        assert_eq!(h.tick(&mut node), Running);
+        assert_eq!(h.tick(&mut node), Running);
+        assert_eq!(h.tick(&mut node), Done);
+    }
+
+    #[test]
+    fn timeout_cancels_child() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut node =
+            Decorator::timeout(Duration::from_millis(20), Stub::new("a", &[Running], &log));
+
+        assert_eq!(h.tick(&mut node), Running);
+        std::thread::sleep(Duration::from_millis(40));
+
+        assert_eq!(h.tick(&mut node), Failed);
+        assert_eq!(log.take(), ["a", "a cancelled"]);
+        assert_eq!(path(&node), ["timeout"]);
+
+        // The timer starts again on the next run
+        assert_eq!(h.tick(&mut node), Running);
+    }
+
+    #[test]
+    fn guard_cancels_child_when_false() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let mut node = Decorator::guard(|world| world.ping < 100, Stub::new("a", &[Running], &log));
+
+        assert_eq!(h.tick(&mut node), Running);
+        assert_eq!(path(&node), ["guard", "a"]);
+
+        h.world.ping = 200;
+        assert_eq!(h.tick(&mut node), Failed);
+        assert_eq!(log.take(), ["a", "a cancelled"]);
+        assert_eq!(path(&node), ["guard"]);
+    }
+}
@@ -0,0 +1,114 @@
+use super::Node;
+use crate::action::{Action, ActionContext, ActionStatus};
+use crate::game::World;
+use crate::ClientEvent;
+
+/// Succeeds if the check holds for the current world
+/// and fails otherwise. Never runs for more than one
+/// tick.
+pub struct Condition<F> {
+    name: String,
+    check: F,
+}
+
+impl<F> Condition<F>
+where
+    F: Fn(&World) -> bool + Send,
+{
+    pub fn new(check: F) -> Self {
+        Self {
+            name: "condition".to_owned(),
+            check,
+        }
+    }
+
+    pub fn named<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+}
+
+impl<F> Node for Condition<F>
+where
+    F: Fn(&World) -> bool + Send,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, _: &ClientEvent) -> ActionStatus {
+        match (self.check)(ctx.world()) {
+            true => ActionStatus::Done,
+            false => ActionStatus::Failed,
+        }
+    }
+
+    fn cancel(&mut self, _: &mut ActionContext) {}
+
+    fn active_path(&self, _: &mut Vec<String>) {}
+}
+
+/// Runs an action.
+///
+/// A fresh action is created from the current world
+/// every time that the leaf starts, so e.g. a leaf that
+/// follows the nearest enemy will pick a new enemy each
+/// time it is run.
+pub struct Leaf<F, A> {
+    name: String,
+    make: F,
+    action: Option<A>,
+}
+
+impl<F, A> Leaf<F, A>
+where
+    F: FnMut(&World) -> A + Send,
+    A: Action,
+{
+    pub fn new(make: F) -> Self {
+        Self {
+            name: "action".to_owned(),
+            make,
+            action: None,
+        }
+    }
+
+    pub fn named<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+}
+
+impl<F, A> Node for Leaf<F, A>
+where
+    F: FnMut(&World) -> A + Send,
+    A: Action,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, evt: &ClientEvent) -> ActionStatus {
+        let make = &mut self.make;
+        let action = self.action.get_or_insert_with(|| make(ctx.world()));
+        let status = action.tick(ctx, evt);
+
+        if status != ActionStatus::Running {
+            self.action = None;
+        }
+
+        status
+    }
+
+    fn cancel(&mut self, ctx: &mut ActionContext) {
+        if let Some(mut action) = self.action.take() {
+            action.cancel(ctx);
+        }
+    }
+
+    fn active_path(&self, path: &mut Vec<String>) {
+        if let Some(ref action) = self.action {
+            path.push(format!("{} ({})", self.name, action.name()));
+        }
+    }
+}
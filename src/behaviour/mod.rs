@@ -0,0 +1,165 @@
+//! Behaviour trees for bot AI.
+//!
+//! A tree is built out of [`Node`]s. Composite nodes
+//! ([`Sequence`], [`Selector`] and [`Parallel`]) decide
+//! which of their children run, [`Decorator`]s change
+//! the result of a single child, [`Condition`]s check
+//! the world and [`Leaf`]s run an [`Action`].
+//!
+//! The whole tree is itself an action, so it is run by
+//! adding a [`BehaviourTree`] to an [`ActionSet`]. It
+//! is ticked with every event, which means conditions
+//! are checked against the latest `World` at least once
+//! per frame.
+//!
+//! [`Action`]: crate::Action
+//! [`ActionSet`]: crate::ActionSet
+
+mod composite;
+mod decorator;
+mod leaf;
+#[cfg(test)]
+mod stub;
+
+pub use self::composite::{Parallel, ParallelPolicy, Selector, Sequence};
+pub use self::decorator::{Decorator, DecoratorKind};
+pub use self::leaf::{Condition, Leaf};
+
+use crate::action::{Action, ActionContext, ActionStatus};
+use crate::ClientEvent;
+
+/// A node within a behaviour tree.
+pub trait Node: Send {
+    fn name(&self) -> &str;
+
+    /// Tick the node. This has the same meaning as
+    /// [`Action::tick`]. A node that returns anything
+    /// other than `Running` should reset itself so that
+    /// it can be run again.
+    ///
+    /// [`Action::tick`]: crate::Action::tick
+    fn tick(&mut self, ctx: &mut ActionContext, evt: &ClientEvent) -> ActionStatus;
+
+    /// Stop the node (and any running children) and
+    /// reset it.
+    fn cancel(&mut self, ctx: &mut ActionContext);
+
+    /// Push the names of this node and any of its running
+    /// descendants onto `path`.
+    fn active_path(&self, path: &mut Vec<String>);
+
+    fn boxed(self) -> Box<dyn Node>
+    where
+        Self: Sized + 'static,
+    {
+        Box::new(self)
+    }
+}
+
+/// The root of a behaviour tree.
+pub struct BehaviourTree {
+    root: Box<dyn Node>,
+    trace: bool,
+    last_path: Vec<String>,
+}
+
+impl BehaviourTree {
+    pub fn new<N: Node + 'static>(root: N) -> Self {
+        Self {
+            root: Box::new(root),
+            trace: false,
+            last_path: vec![],
+        }
+    }
+
+    /// Log which branch of the tree is active whenever
+    /// it changes.
+    pub fn with_tracing(mut self, trace: bool) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// The names of the nodes that are currently running,
+    /// from the root down.
+    pub fn active_path(&self) -> Vec<String> {
+        let mut path = vec![];
+        self.root.active_path(&mut path);
+        path
+    }
+}
+
+impl Action for BehaviourTree {
+    fn name(&self) -> &str {
+        self.root.name()
+    }
+
+    fn tick(&mut self, ctx: &mut ActionContext, evt: &ClientEvent) -> ActionStatus {
+        let status = self.root.tick(ctx, evt);
+
+        if self.trace {
+            let path = self.active_path();
+
+            if path != self.last_path {
+                info!("Behaviour tree active branch: {}", path.join(" > "));
+                self.last_path = path;
+            }
+            if status != ActionStatus::Running {
+                info!("Behaviour tree finished with {:?}", status);
+            }
+        }
+
+        status
+    }
+
+    fn cancel(&mut self, ctx: &mut ActionContext) {
+        self.root.cancel(ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stub::{Harness, Log, Stub};
+    use super::*;
+    use crate::action::ActionStatus::*;
+
+    use std::time::Instant;
+
+    #[test]
+    fn tracing_follows_active_branch() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let frame = ClientEvent::Frame(Instant::now());
+        let mut tree = BehaviourTree::new(
+            Sequence::new(vec![
+                Stub::new("a", &[Done], &log).boxed(),
+                Stub::new("b", &[Running, Done], &log).boxed(),
+            ])
+            .named("root"),
+        )
+        .with_tracing(true);
+
+        assert_eq!(tree.tick(&mut h.ctx(), &frame), Running);
+        assert_eq!(tree.active_path(), ["root", "b"]);
+        assert_eq!(tree.last_path, tree.active_path());
+
+        assert_eq!(tree.tick(&mut h.ctx(), &frame), Done);
+        assert_eq!(tree.active_path(), ["root"]);
+        assert_eq!(tree.last_path, ["root"]);
+    }
+
+    #[test]
+    fn cancel_reaches_running_leaf() {
+        let log = Log::new();
+        let mut h = Harness::new();
+        let frame = ClientEvent::Frame(Instant::now());
+        let mut tree =
+            BehaviourTree::new(Selector::new(
+                vec![Stub::new("a", &[Running], &log).boxed()],
+            ));
+
+        tree.tick(&mut h.ctx(), &frame);
+        tree.cancel(&mut h.ctx());
+        assert_eq!(log.take(), ["a", "a cancelled"]);
+        assert_eq!(tree.active_path(), ["selector"]);
+    }
+}
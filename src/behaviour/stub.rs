@@ -0,0 +1,116 @@
+//! Nodes with scripted results for testing the
+//! composite nodes and decorators.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use super::Node;
+use crate::action::{ActionContext, ActionStatus, KeyArbiter};
+use crate::game::World;
+use crate::protocol::ClientPacket;
+use crate::ClientEvent;
+
+/// Everything that happened to a group of stubs, in
+/// order. Ticks are recorded as the name of the stub
+/// and cancellations as `"<name> cancelled"`.
+#[derive(Clone, Default)]
+pub(crate) struct Log(Arc<Mutex<Vec<String>>>);
+
+impl Log {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take everything logged so far.
+    pub fn take(&self) -> Vec<String> {
+        std::mem::replace(&mut *self.0.lock().unwrap(), vec![])
+    }
+
+    fn push(&self, entry: String) {
+        self.0.lock().unwrap().push(entry);
+    }
+}
+
+/// A node that returns a fixed sequence of statuses.
+/// Once there is only one left it keeps returning it.
+pub(crate) struct Stub {
+    name: String,
+    statuses: VecDeque<ActionStatus>,
+    running: bool,
+    log: Log,
+}
+
+impl Stub {
+    pub fn new(name: &str, statuses: &[ActionStatus], log: &Log) -> Self {
+        assert!(!statuses.is_empty());
+
+        Self {
+            name: name.to_owned(),
+            statuses: statuses.iter().cloned().collect(),
+            running: false,
+            log: log.clone(),
+        }
+    }
+}
+
+impl Node for Stub {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tick(&mut self, _: &mut ActionContext, _: &ClientEvent) -> ActionStatus {
+        self.log.push(self.name.clone());
+
+        let status = match self.statuses.len() {
+            1 => self.statuses[0],
+            _ => self.statuses.pop_front().unwrap(),
+        };
+        self.running = status == ActionStatus::Running;
+        status
+    }
+
+    fn cancel(&mut self, _: &mut ActionContext) {
+        self.log.push(format!("{} cancelled", self.name));
+        self.running = false;
+    }
+
+    fn active_path(&self, path: &mut Vec<String>) {
+        if self.running {
+            path.push(self.name.clone());
+        }
+    }
+}
+
+/// Everything needed to build an [`ActionContext`].
+#[derive(Default)]
+pub(crate) struct Harness {
+    pub world: World,
+    arbiter: KeyArbiter,
+    packets: Vec<ClientPacket>,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ctx(&mut self) -> ActionContext {
+        ActionContext::new(&self.world, 0, &mut self.arbiter, &mut self.packets)
+    }
+
+    pub fn tick(&mut self, node: &mut dyn Node) -> ActionStatus {
+        node.tick(&mut self.ctx(), &ClientEvent::Frame(Instant::now()))
+    }
+
+    pub fn cancel(&mut self, node: &mut dyn Node) {
+        node.cancel(&mut self.ctx());
+    }
+}
+
+/// The names of the running nodes under `node`.
+pub(crate) fn path(node: &dyn Node) -> Vec<String> {
+    let mut path = vec![];
+    node.active_path(&mut path);
+    path
+}
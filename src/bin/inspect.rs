@@ -0,0 +1,392 @@
+//! Dump the packets going between a bot and a server
+//! as JSON, one packet per line. This can either log
+//! in to a live server or decode a capture that was
+//! written by a `Recorder`.
+//!
+//! Each line looks like
+//! `{"time":1.234,"direction":"in","type":"PlayerUpdate","packet":{...}}`
+//! where `time` is in seconds since the start of the
+//! session or capture.
+
+#![feature(futures_api, await_macro, async_await)]
+
+extern crate airmash_client;
+extern crate airmash_protocol_v5;
+extern crate clap;
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_json;
+extern crate tokio;
+extern crate url;
+
+use airmash_client::protocol::*;
+use airmash_client::*;
+use airmash_protocol_v5::ProtocolV5;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+use tokio::r#await;
+use url::Url;
+
+/// Which packets should be written out.
+#[derive(Clone, Debug, Default)]
+struct Filter {
+    /// Packet type names. Empty allows all of them.
+    types: Vec<String>,
+    /// Player ids. Empty allows all of them.
+    players: Vec<u16>,
+}
+
+impl Filter {
+    fn allows_type(&self, name: &str) -> bool {
+        self.types.is_empty() || self.types.iter().any(|x| x.eq_ignore_ascii_case(name))
+    }
+
+    fn allows_players(&self, players: &[u16]) -> bool {
+        self.players.is_empty() || self.players.iter().any(|id| players.contains(id))
+    }
+}
+
+/// The ids of every player that a server packet refers
+/// to. Mob ids, such as `MobUpdate.id` or the ids of the
+/// projectiles in `PlayerFire`, are left out.
+fn server_players(packet: &ServerPacket) -> Vec<u16> {
+    use self::ServerPacket::*;
+
+    match packet {
+        Login(p) => Some(p.id)
+            .into_iter()
+            .chain(p.players.iter().map(|x| x.id))
+            .map(|x| x.0)
+            .collect(),
+        PlayerNew(p) => vec![p.id.0],
+        PlayerLeave(p) => vec![p.id.0],
+        PlayerUpdate(p) => vec![p.id.0],
+        PlayerFire(p) => vec![p.id.0],
+        PlayerRespawn(p) => vec![p.id.0],
+        PlayerFlag(p) => vec![p.id.0],
+        PlayerHit(p) => Some(p.owner)
+            .into_iter()
+            .chain(p.players.iter().map(|x| x.id))
+            .map(|x| x.0)
+            .collect(),
+        PlayerKill(p) => Some(p.id).into_iter().chain(p.killer).map(|x| x.0).collect(),
+        PlayerType(p) => vec![p.id.0],
+        PlayerLevel(p) => vec![p.id.0],
+        PlayerReteam(p) => p.players.iter().map(|x| x.id.0).collect(),
+        GameFlag(p) => p.id.into_iter().map(|x| x.0).collect(),
+        GameSpectate(p) => vec![p.id.0],
+        EventRepel(p) => Some(p.id)
+            .into_iter()
+            .chain(p.players.iter().map(|x| x.id))
+            .map(|x| x.0)
+            .collect(),
+        EventBoost(p) => vec![p.id.0],
+        EventBounce(p) => vec![p.id.0],
+        EventStealth(p) => vec![p.id.0],
+        EventLeaveHorizon(p) => match p.ty {
+            LeaveHorizonType::Player => vec![p.id],
+            _ => vec![],
+        },
+        ScoreUpdate(p) => vec![p.id.0],
+        ScoreBoard(p) => p
+            .data
+            .iter()
+            .map(|x| x.id)
+            .chain(p.rankings.iter().map(|x| x.id))
+            .map(|x| x.0)
+            .collect(),
+        ScoreDetailedFFA(p) => p.scores.iter().map(|x| x.id.0).collect(),
+        ScoreDetailedCTF(p) => p.scores.iter().map(|x| x.id.0).collect(),
+        ScoreDetailedBTR(p) => p.scores.iter().map(|x| x.id.0).collect(),
+        ChatTeam(p) => vec![p.id.0],
+        ChatPublic(p) => vec![p.id.0],
+        ChatSay(p) => vec![p.id.0],
+        ChatWhisper(p) => vec![p.from.0, p.to.0],
+        ChatVoteMutePassed(p) => vec![p.id.0],
+        _ => vec![],
+    }
+}
+
+/// The ids of every player that a client packet
+/// refers to.
+fn client_players(packet: &ClientPacket) -> Vec<u16> {
+    match packet {
+        ClientPacket::Whisper(p) => vec![p.id.0],
+        ClientPacket::VoteMute(p) => vec![p.id.0],
+        _ => vec![],
+    }
+}
+
+/// Pull the packet body out of the serialized enum.
+/// Packets without a body (e.g. `Ack`) become null.
+fn packet_body(value: Value) -> Value {
+    match value {
+        Value::Object(map) => map.into_iter().next().map(|(_, v)| v).unwrap_or(Value::Null),
+        _ => Value::Null,
+    }
+}
+
+type Decoded = (&'static str, Vec<u16>, Value);
+
+/// Decodes frames and writes them out as JSON lines.
+struct Inspector {
+    filter: Filter,
+    out: Box<dyn Write + Send>,
+    start: Instant,
+}
+
+impl Inspector {
+    fn new(filter: Filter, out: Box<dyn Write + Send>) -> Self {
+        Self {
+            filter,
+            out,
+            start: Instant::now(),
+        }
+    }
+
+    /// Decode a frame into its type name, the players
+    /// that it refers to and the packet itself.
+    fn decode(data: &[u8], direction: Direction) -> Result<Decoded, String> {
+        let (name, players, value) = match direction {
+            Direction::Inbound => {
+                let packet = ProtocolV5 {}
+                    .deserialize_server(data)
+                    .map_err(|e| e.to_string())?;
+                (
+                    server_packet_name(&packet),
+                    server_players(&packet),
+                    serde_json::to_value(&packet),
+                )
+            }
+            Direction::Outbound => {
+                let packet = ProtocolV5 {}
+                    .deserialize_client(data)
+                    .map_err(|e| e.to_string())?;
+                (
+                    client_packet_name(&packet),
+                    client_players(&packet),
+                    serde_json::to_value(&packet),
+                )
+            }
+        };
+
+        Ok((name, players, packet_body(value.map_err(|e| e.to_string())?)))
+    }
+
+    fn write(&mut self, time: Duration, direction: Direction, data: &[u8]) -> io::Result<()> {
+        let time = time.as_secs() as f64 + time.subsec_micros() as f64 * 1e-6;
+        let dir = match direction {
+            Direction::Inbound => "in",
+            Direction::Outbound => "out",
+        };
+
+        let line = match Self::decode(data, direction) {
+            Ok((name, players, packet)) => {
+                if !self.filter.allows_type(name) || !self.filter.allows_players(&players) {
+                    return Ok(());
+                }
+
+                json!({
+                    "time": time,
+                    "direction": dir,
+                    "type": name,
+                    "packet": packet,
+                })
+            }
+            // Frames that can't be decoded are the most
+            // interesting ones when debugging, so only
+            // drop them when filtering by type.
+            Err(e) => {
+                if !self.filter.types.is_empty() {
+                    return Ok(());
+                }
+
+                json!({
+                    "time": time,
+                    "direction": dir,
+                    "error": e,
+                    "len": data.len(),
+                })
+            }
+        };
+
+        writeln!(self.out, "{}", line)?;
+        self.out.flush()
+    }
+}
+
+impl FrameSink for Inspector {
+    fn frame(&mut self, direction: Direction, data: &[u8]) -> io::Result<()> {
+        let time = Instant::now() - self.start;
+        self.write(time, direction, data)
+    }
+}
+
+fn read_capture(path: &str, mut inspector: Inspector) -> Result<(), Box<Error>> {
+    for record in RecordReader::open(path)? {
+        let record = record?;
+
+        inspector.write(record.time, record.direction, &record.data)?;
+    }
+
+    Ok(())
+}
+
+async fn inspect_inner(
+    server: Url,
+    name: String,
+    flag: String,
+    inspector: Inspector,
+) -> Result<(), Box<Error + 'static>> {
+    let mut client = match server.scheme() {
+        "wss" => r#await!(ClientBase::new_insecure(server))?,
+        _ => r#await!(ClientBase::new(server))?,
+    };
+    client.set_recorder(inspector);
+
+    r#await!(client.send(client::Login {
+        flag,
+        name,
+        session: "none".to_owned(),
+        horizon_x: 3000,
+        horizon_y: 3000,
+        protocol: 5,
+    }))?;
+
+    // The recorder sees every raw frame before the client
+    // decodes it, so frames that fail to decode are still
+    // written out. We just need to keep the connection
+    // going.
+    while r#await!(client.next())?.is_some() {}
+
+    Ok(())
+}
+
+async fn inspect(server: Url, name: String, flag: String, inspector: Inspector) {
+    if let Err(e) = r#await!(inspect_inner(server, name, flag, inspector)) {
+        error!("The connection ended with an error: {}", e);
+    }
+}
+
+fn main() {
+    use clap::*;
+
+    env_logger::init();
+
+    let args = App::new("Packet Inspector")
+        .about("Dumps decoded packets as JSON lines")
+        .author("STEAMROLLER")
+        .arg(
+            Arg::with_name("server")
+                .long("server")
+                .help("The server to connect to.")
+                .default_value("ws://localhost:3501")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("name")
+                .long("name")
+                .short("n")
+                .help("The name to log in with.")
+                .default_value("INSPECTOR")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("flag")
+                .long("flag")
+                .short("f")
+                .help("The flag to log in with.")
+                .default_value("UN")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("capture")
+                .long("capture")
+                .short("c")
+                .help("Read packets from a capture file instead of connecting to a server.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("type")
+                .long("type")
+                .short("t")
+                .help("Only show packets of this type (e.g. PlayerUpdate). Can be given multiple times.")
+                .multiple(true)
+                .number_of_values(1)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("player")
+                .long("player")
+                .short("p")
+                .help("Only show packets that mention this player id. Can be given multiple times.")
+                .multiple(true)
+                .number_of_values(1)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .help("Write to this file instead of stdout.")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    let mut filter = Filter::default();
+    if let Some(types) = args.values_of("type") {
+        filter.types = types.map(|x| x.to_owned()).collect();
+    }
+    if let Some(players) = args.values_of("player") {
+        for player in players {
+            match player.parse() {
+                Ok(id) => filter.players.push(id),
+                Err(e) => {
+                    eprintln!("Invalid player id {}: {}", player, e);
+                    return;
+                }
+            }
+        }
+    }
+
+    let out: Box<dyn Write + Send> = match args.value_of("output") {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(e) => {
+                eprintln!("Unable to create {}: {}", path, e);
+                return;
+            }
+        },
+        None => Box::new(io::stdout()),
+    };
+    let inspector = Inspector::new(filter, out);
+
+    if let Some(path) = args.value_of("capture") {
+        if let Err(e) = read_capture(path, inspector) {
+            eprintln!("Failed to read capture {}: {}", path, e);
+        }
+        return;
+    }
+
+    // These all have default values.
+    let server = args.value_of("server").expect("No server provided!");
+    let name = args.value_of("name").expect("No name provided!");
+    let flag = args.value_of("flag").expect("No flag provided!");
+
+    let server = match server.parse() {
+        Ok(url) => url,
+        Err(e) => {
+            eprintln!("An error occurred while parsing the server URL:\n{}", e);
+            return;
+        }
+    };
+
+    tokio::run_async(inspect(server, name.to_owned(), flag.to_owned(), inspector));
+}
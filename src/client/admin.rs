@@ -0,0 +1,152 @@
+//! Admin commands for servers that support them.
+//!
+//! These are only available with the `admin` feature.
+//! They are sent as normal `Command` packets and follow
+//! the command set implemented by the stand-in server in
+//! `test_server` (enabled by the `test-server` feature):
+//!
+//! | Command         | Data                 |
+//! |-----------------|----------------------|
+//! | `teleport`      | `<player> <x> <y>`   |
+//! | `team`          | `<player> <team>`    |
+//! | `spawn-upgrade` | `<mob type> <x> <y>` |
+//! | `kick`          | `<player>`           |
+//! | `game-state`    | `start|end|reset`    |
+//!
+//! The server answers each one with a `CommandReply`
+//! whose text is `<command>: <message>` on success and
+//! `<command>: error: <reason>` on failure. Servers that
+//! don't know a command send an `UnknownCommand` error
+//! instead, the same as for any other command.
+
+use airmash_protocol::*;
+
+use super::commands::COMMAND_TIMEOUT;
+use super::*;
+
+/// The reply that the server sent to an admin command.
+#[derive(Clone, Debug)]
+pub struct AdminReply {
+    pub ty: CommandReplyType,
+    /// The message with the command name stripped off.
+    pub text: String,
+}
+
+/// States that an admin can put the game into.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AdminGameState {
+    /// Start a new round.
+    Start,
+    /// End the current round.
+    End,
+    /// Reset scores and respawn everyone.
+    Reset,
+}
+
+impl AdminGameState {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            AdminGameState::Start => "start",
+            AdminGameState::End => "end",
+            AdminGameState::Reset => "reset",
+        }
+    }
+}
+
+/// Split the reply to the admin command `com` into
+/// its message or error reason. Returns `None` if the
+/// reply is for some other command.
+pub(crate) fn parse_reply(com: &str, text: &str) -> Option<Result<String, String>> {
+    if !text.starts_with(com) {
+        return None;
+    }
+
+    let rest = text[com.len()..].trim_start();
+    if !rest.starts_with(':') {
+        return None;
+    }
+
+    let msg = rest[1..].trim();
+    if msg.starts_with("error:") {
+        Some(Err(msg["error:".len()..].trim().to_owned()))
+    } else {
+        Some(Ok(msg.to_owned()))
+    }
+}
+
+impl<T: Client> ImplClient<T> {
+    /// Send an admin command and wait for the server to
+    /// reply to it.
+    ///
+    /// Fails with [`ClientError::CommandFailed`] if the
+    /// server reports an error for the command. Replies
+    /// to other commands are skipped.
+    pub async fn admin_command(&mut self, com: &str, data: String) -> ClientResult<AdminReply> {
+        r#await!(self.send_command(com, data))?;
+        let (ty, reply) = r#await!(self.wait_for_reply(COMMAND_TIMEOUT, |p| match p {
+            ServerPacket::CommandReply(p) => parse_reply(com, &p.text).map(|r| (p.ty, r)),
+            _ => None,
+        }))?;
+
+        match reply {
+            Ok(text) => Ok(AdminReply { ty, text }),
+            Err(reason) => Err(ClientError::CommandFailed(reason)),
+        }
+    }
+
+    /// Move a player to the given position.
+    pub async fn admin_teleport(&mut self, player: u16, pos: Position) -> ClientResult<AdminReply> {
+        let data = format!("{} {} {}", player, pos.x.inner(), pos.y.inner());
+
+        r#await!(self.admin_command("teleport", data))
+    }
+
+    /// Move a player to a different team.
+    pub async fn admin_set_team(&mut self, player: u16, team: Team) -> ClientResult<AdminReply> {
+        let data = format!("{} {}", player, team.0);
+
+        r#await!(self.admin_command("team", data))
+    }
+
+    /// Spawn an upgrade, shield or inferno pickup at the
+    /// given position.
+    pub async fn admin_spawn_upgrade(
+        &mut self,
+        ty: MobType,
+        pos: Position,
+    ) -> ClientResult<AdminReply> {
+        let data = format!("{} {} {}", ty as u8, pos.x.inner(), pos.y.inner());
+
+        r#await!(self.admin_command("spawn-upgrade", data))
+    }
+
+    /// Kick a player from the server.
+    pub async fn admin_kick(&mut self, player: u16) -> ClientResult<AdminReply> {
+        r#await!(self.admin_command("kick", player.to_string()))
+    }
+
+    /// Change the state of the game.
+    pub async fn admin_game_state(&mut self, state: AdminGameState) -> ClientResult<AdminReply> {
+        r#await!(self.admin_command("game-state", state.as_str().to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reply_for_command() {
+        assert_eq!(parse_reply("kick", "kick: 5 kicked"), Some(Ok("5 kicked".to_owned())));
+        assert_eq!(
+            parse_reply("team", "team: error: no such player"),
+            Some(Err("no such player".to_owned()))
+        );
+    }
+
+    #[test]
+    fn reply_for_other_command() {
+        assert_eq!(parse_reply("team", "teleport: moved 5"), None);
+        assert_eq!(parse_reply("team", "teams are unbalanced"), None);
+    }
+}
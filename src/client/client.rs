@@ -1,8 +1,6 @@
 use url::Url;
 
-use std::f32::consts::PI;
 use std::future::Future;
-use std::ops::{Add, Rem};
 use std::r#await as std_await;
 use std::time::{Duration, Instant};
 
@@ -12,9 +10,8 @@ use airmash_protocol::*;
 use airmash_protocol_v5::ProtocolV5;
 
 use super::{ClientBase, ClientError, ClientResult};
-use crate::consts;
 use crate::future::BoxedFuture;
-use crate::game::World;
+use crate::game::{ChatMessage, GameEvent, World};
 use crate::ClientEvent;
 
 pub type ClientFuture<'a, T> = Box<dyn Future<Output = Result<T, ClientError>> + Send + 'a>;
@@ -24,6 +21,10 @@ pub trait Client {
     fn world_mut(&mut self) -> &mut World;
     fn _next<'a>(&'a mut self) -> ClientFuture<'a, Option<ClientEvent>>;
     fn _send_buf<'a>(&'a mut self, buf: Vec<u8>) -> ClientFuture<'a, ()>;
+
+    /// Called with every packet sent through
+    /// [`ImplClient::send`] before it gets serialized.
+    fn _on_send(&mut self, _packet: &ClientPacket) {}
 }
 
 // Hack as described here https://github.com/rust-lang/rfcs/issues/1971#issuecomment-294282433
@@ -43,6 +44,9 @@ impl<T: Client> ImplClient<T> {
     pub(self) fn _send_buf<'a>(&'a mut self, buf: Vec<u8>) -> ClientFuture<'a, ()> {
         self.0._send_buf(buf)
     }
+    pub(self) fn _on_send(&mut self, packet: &ClientPacket) {
+        self.0._on_send(packet)
+    }
 }
 
 impl Client {
@@ -57,7 +61,17 @@ impl Client {
 // Base functions
 impl<T: Client> ImplClient<T> {
     pub async fn next(&mut self) -> ClientResult<Option<ClientEvent>> {
-        r#await!(BoxedFuture::new(self._next()))
+        let evt = r#await!(BoxedFuture::new(self._next()))?;
+
+        let upgrade = match evt {
+            Some(ref evt) => self.strategy_upgrade(evt),
+            None => None,
+        };
+        if let Some(ty) = upgrade {
+            r#await!(self.send_upgrade(ty))?;
+        }
+
+        Ok(evt)
     }
 
     pub async fn send_buf(&mut self, buf: Vec<u8>) -> ClientResult<()> {
@@ -68,7 +82,10 @@ impl<T: Client> ImplClient<T> {
     where
         P: Into<ClientPacket> + 'static,
     {
-        let packets: Vec<_> = ProtocolV5 {}.serialize_client(&packet.into())?.collect();
+        let packet = packet.into();
+        self._on_send(&packet);
+
+        let packets: Vec<_> = ProtocolV5 {}.serialize_client(&packet)?.collect();
 
         for buf in packets {
             r#await!(self.send_buf(buf))?;
@@ -90,6 +107,7 @@ impl<T: Client> ImplClient<T> {
 
         let seq = self.world().key_seq;
         self.world_mut().key_seq += 1;
+        self.world_mut().keys.set(key, state);
 
         r#await!(self.send(Key { key, seq, state }))
     }
@@ -126,85 +144,6 @@ impl<T: Client> ImplClient<T> {
         r#await!(self.wait_until(Instant::now() + dur))
     }
 
-    /// Turn the plane by a given rotation.
-    ///
-    /// This is a best effort implementation as it is
-    /// impossible to turn exactly any given amount.
-    /// This method may overshoot in cases where network
-    /// ping changes significantly during the execution
-    /// of the turn.
-    pub async fn turn(&mut self, rot: Rotation) -> ClientResult<()> {
-        let rotrate = consts::rotation_rate(self.world().get_me().plane);
-        let time: Duration = (rot.abs() / rotrate).min(Time::new(100.0)).into();
-
-        let key = if rot < 0.0.into() {
-            KeyCode::Left
-        } else {
-            KeyCode::Right
-        };
-
-        if rot.inner().abs() < 0.05 {
-            return Ok(());
-        }
-
-        r#await!(self.press_key(key))?;
-        r#await!(self.wait(time))?;
-        r#await!(self.release_key(key))?;
-
-        Ok(())
-    }
-
-    /// Turn to a given angle.
-    ///
-    /// This is a best effort implementation as it is
-    /// impossible to turn exactly any given amount.
-    /// This method may overshoot in cases where network
-    /// ping changes significantly during the execution
-    /// of the turn.
-    pub async fn turn_to(&mut self, tgt: Rotation) -> ClientResult<()> {
-        /// Utility since rust doesn't provide fmod
-        fn fmod<T>(a: T, b: T) -> T
-        where
-            T: Rem<Output = T> + Add<Output = T> + Copy,
-        {
-            (a % b + b) % b
-        }
-
-        // Determine the shortest turn angle
-        // The basic idea comes from this SO answer
-        // https://stackoverflow.com/questions/9505862/shortest-distance-between-two-degree-marks-on-a-circle
-        let rot = self.world().get_me().rot;
-        let pi = Rotation::new(PI);
-        let pi2 = 2.0 * pi;
-        let mut dist = fmod(tgt - rot, pi2);
-
-        if dist > pi {
-            dist -= pi2;
-        }
-
-        r#await!(self.turn(dist))
-    }
-
-    /// Point the plane at a given point.
-    ///
-    /// This is a best effort implementation as it is
-    /// impossible to turn exactly any given amount.
-    /// This method may overshoot in cases where network
-    /// ping changes significantly during the execution
-    /// of the turn.
-    pub async fn point_at(&mut self, pos: Position) -> ClientResult<()> {
-        use crate::consts::BASE_DIR;
-
-        let rel = (pos - self.world().get_me().pos).normalized();
-        let mut angle = Vector2::dot(rel, BASE_DIR).acos();
-
-        if rel.x < 0.0.into() {
-            angle = 2.0 * PI - angle;
-        }
-
-        r#await!(self.turn_to(angle.into()))
-    }
-
     /// Say something in chat
     pub async fn chat(&mut self, text: String) -> ClientResult<()> {
         r#await!(self.send(client::Chat { text }))
@@ -220,6 +159,38 @@ impl<T: Client> ImplClient<T> {
         r#await!(self.send(client::Say { text }))
     }
 
+    /// Send a private message to another player
+    pub async fn whisper(&mut self, player: u16, text: String) -> ClientResult<()> {
+        r#await!(self.send(client::Whisper {
+            id: player.into(),
+            text
+        }))
+    }
+
+    /// Process events until a chat message matching
+    /// `predicate` is received. If the connection
+    /// closes first then it will return `None`.
+    ///
+    /// Only messages received after this is called
+    /// are considered, use [`World::chat`] to look at
+    /// older ones.
+    ///
+    /// [`World::chat`]: crate::World::chat
+    pub async fn wait_for_chat<F>(&mut self, mut predicate: F) -> ClientResult<Option<ChatMessage>>
+    where
+        F: FnMut(&ChatMessage) -> bool + Send,
+    {
+        while let Some(evt) = r#await!(self.next())? {
+            if let ClientEvent::Game(GameEvent::Chat(msg)) = evt {
+                if predicate(&msg) {
+                    return Ok(Some(msg));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
     /// Wait to receive a login packet. If the
     /// connection closes before receiving the
     /// packet then it will return `None`.
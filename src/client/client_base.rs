@@ -1,5 +1,6 @@
 use url::Url;
 
+use std::collections::VecDeque;
 use std::net::ToSocketAddrs;
 use std::ops::{Deref, DerefMut};
 use std::time::{Duration, Instant};
@@ -14,15 +15,16 @@ use airmash_protocol::*;
 use airmash_protocol_v5::ProtocolV5;
 
 use crate::future::BoxedFuture;
-use crate::game::World;
+use crate::game::{ClientKeyState, World};
+use crate::record::{Direction, FrameSink};
 use crate::ClientEvent;
-use crate::{Client, ClientError, ClientFuture, ClientResult, ImplClient};
+use crate::{Client, ClientError, ClientFuture, ClientResult, ImplClient, ReconnectPolicy};
 
 static TICKER_TIME: Duration = Duration::from_millis(16);
 
 type FromFn<T, U> = fn(T) -> U;
-type ParseTimeFn = fn(std::time::Instant) -> ClientEvent;
-type ParsePacketFn = fn(tungstenite::Message) -> Result<Option<ClientEvent>, ClientError>;
+type MessageFn = fn(tungstenite::Message) -> StreamItem;
+type TimeFn = fn(std::time::Instant) -> StreamItem;
 
 type WebSocketStream = tokio_tungstenite::WebSocketStream<
     tokio_tungstenite::stream::Stream<
@@ -36,28 +38,49 @@ type ClientSink = futures::stream::SplitSink<WebSocketStream>;
 // This is ugly, but it means that client doesn't need type parameters
 type ClientStream = futures::stream::Fuse<
     futures::stream::Select<
-        futures::stream::FilterMap<
-            futures::stream::AndThen<
+        futures::stream::Chain<
+            futures::stream::Map<
                 futures::stream::MapErr<
                     futures::stream::SplitStream<WebSocketStream>,
                     FromFn<tungstenite::Error, ClientError>,
                 >,
-                ParsePacketFn,
-                Result<Option<ClientEvent>, ClientError>,
+                MessageFn,
             >,
-            fn(Option<ClientEvent>) -> Option<ClientEvent>,
+            futures::stream::Once<StreamItem, ClientError>,
         >,
         futures::stream::Map<
             futures::stream::MapErr<Interval, FromFn<tokio::timer::Error, ClientError>>,
-            ParseTimeFn,
+            TimeFn,
         >,
     >,
 >;
 
+/// Either a raw websocket message or a tick of the
+/// frame timer. Messages are decoded by the client
+/// (instead of within the stream) so that the raw
+/// frames can be recorded first.
+enum StreamItem {
+    Message(Message),
+    Frame(Instant),
+    /// The websocket was closed. The frame timer never
+    /// ends, so this is the only way to notice.
+    Closed,
+}
+
 pub struct ClientBase {
     pub world: World,
     sink: Option<ClientSink>,
     stream: ClientStream,
+    pending: VecDeque<ClientEvent>,
+
+    url: Url,
+    insecure: bool,
+    /// The last login packet sent by the bot, used
+    /// to log in again after reconnecting.
+    login: Option<client::Login>,
+    reconnect: Option<ReconnectPolicy>,
+    disconnected: bool,
+    recorder: Option<Box<dyn FrameSink>>,
 }
 
 impl Client for ClientBase {
@@ -75,12 +98,46 @@ impl Client for ClientBase {
     fn _send_buf<'a>(&'a mut self, buf: Vec<u8>) -> ClientFuture<'a, ()> {
         Box::new(self._send_buf_impl(buf))
     }
+
+    fn _on_send(&mut self, packet: &ClientPacket) {
+        if let ClientPacket::Login(login) = packet {
+            self.login = Some(login.clone());
+        }
+    }
 }
 
 /// async trait backing functions
 impl ClientBase {
     async fn _send_buf_impl(&mut self, buf: Vec<u8>) -> Result<(), ClientError> {
-        let sink = self.sink.take().unwrap();
+        let e = match r#await!(self.write_frame(buf)) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+
+        // While reconnecting the failure belongs to the
+        // current attempt. Otherwise go through the same
+        // path as a failed read, so that the bot gets a
+        // `Disconnected` event and reconnects on the next
+        // call to `next`.
+        if self.disconnected {
+            return Err(e);
+        }
+
+        let evt = self.handle_disconnect(e)?;
+        self.pending.extend(evt);
+
+        Ok(())
+    }
+
+    async fn write_frame(&mut self, buf: Vec<u8>) -> Result<(), ClientError> {
+        self.record_frame(Direction::Outbound, &buf);
+
+        let sink = match self.sink.take() {
+            Some(sink) => sink,
+            // The previous send failed and we haven't
+            // reconnected yet.
+            None => return Err(ClientError::Disconnected),
+        };
         let msg = Message::Binary(buf);
 
         self.sink = Some(r#await!(sink.send(msg))?);
@@ -91,14 +148,35 @@ impl ClientBase {
     async fn _next_impl(&mut self) -> Result<Option<ClientEvent>, ClientError> {
         use self::ClientEvent::*;
 
-        let val = match r#await!(self.stream.next()) {
-            Some(x) => x?,
-            None => return Ok(None),
+        if let Some(evt) = self.pending.pop_front() {
+            return Ok(Some(evt));
+        }
+
+        if self.disconnected {
+            return r#await!(self.reconnect_impl()).map(Some);
+        }
+
+        let val = loop {
+            let item = match r#await!(self.stream.next()) {
+                Some(Ok(StreamItem::Closed)) | None => match self.reconnect {
+                    Some(_) => return self.handle_disconnect(ClientError::Disconnected),
+                    None => return Ok(None),
+                },
+                Some(Ok(x)) => x,
+                Some(Err(e)) => return self.handle_disconnect(e),
+            };
+
+            match self.parse_item(item) {
+                Ok(Some(evt)) => break evt,
+                Ok(None) => continue,
+                Err(e) => return self.handle_disconnect(e),
+            }
         };
 
         match &val {
             Packet(p) => r#await!(self.packet_update(p))?,
             Frame(now) => self.world.update(*now),
+            Disconnected(_) | Reconnected | Game(_) => (),
         }
 
         Ok(Some(val))
@@ -125,7 +203,8 @@ impl ClientBase {
         use self::ServerPacket::*;
         use airmash_protocol::client::Pong;
 
-        self.world.handle_packet(packet);
+        let events = self.world.handle_packet(packet);
+        self.pending.extend(events.into_iter().map(ClientEvent::Game));
 
         match packet {
             Ping(p) => r#await!(self.send(Pong { num: p.num }))?,
@@ -136,46 +215,268 @@ impl ClientBase {
     }
 }
 
+/// Recording
+impl ClientBase {
+    /// Pass every websocket frame sent or received by
+    /// this client to `recorder`.
+    ///
+    /// Usually this will be a [`Recorder`] writing to
+    /// a file which can later be played back using a
+    /// [`ReplayClient`].
+    ///
+    /// [`Recorder`]: crate::Recorder
+    /// [`ReplayClient`]: crate::ReplayClient
+    pub fn set_recorder<R>(&mut self, recorder: R)
+    where
+        R: FrameSink + 'static,
+    {
+        self.recorder = Some(Box::new(recorder));
+    }
+
+    /// Stop recording frames.
+    pub fn clear_recorder(&mut self) {
+        self.recorder = None;
+    }
+
+    /// Record a message as it came off the wire, then
+    /// decode it.
+    fn parse_item(&mut self, item: StreamItem) -> Result<Option<ClientEvent>, ClientError> {
+        let msg = match item {
+            StreamItem::Frame(now) => return Ok(Some(ClientEvent::Frame(now))),
+            StreamItem::Message(msg) => msg,
+            StreamItem::Closed => return Err(ClientError::Disconnected),
+        };
+
+        match &msg {
+            Message::Binary(buf) => self.record_frame(Direction::Inbound, buf),
+            // Recorded so that a capture shows what the
+            // server sent before we dropped the connection.
+            Message::Text(txt) => self.record_frame(Direction::Inbound, txt.as_bytes()),
+            Message::Ping(_) | Message::Pong(_) => (),
+        }
+
+        parse_packet(msg)
+    }
+
+    fn record_frame(&mut self, direction: Direction, buf: &[u8]) {
+        let result = match self.recorder.as_mut() {
+            Some(recorder) => recorder.frame(direction, buf),
+            None => return,
+        };
+
+        if let Err(e) = result {
+            // A broken capture shouldn't take the bot down
+            // with it, so just stop recording.
+            error!("Failed to record frame, recording stopped: {}", e);
+            self.recorder = None;
+        }
+    }
+}
+
+/// Reconnection
+impl ClientBase {
+    /// Enable automatic reconnection with the given policy.
+    ///
+    /// When the connection drops the client will produce a
+    /// [`Disconnected`] event, then re-dial the server, send
+    /// the last login packet again and re-press any keys that
+    /// were held. Once logged in again it will produce a
+    /// [`Reconnected`] event.
+    ///
+    /// [`Disconnected`]: crate::ClientEvent::Disconnected
+    /// [`Reconnected`]: crate::ClientEvent::Reconnected
+    pub fn set_reconnect_policy(&mut self, policy: ReconnectPolicy) {
+        self.reconnect = Some(policy);
+    }
+
+    /// Disable automatic reconnection.
+    pub fn clear_reconnect_policy(&mut self) {
+        self.reconnect = None;
+    }
+
+    fn handle_disconnect(&mut self, e: ClientError) -> ClientResult<Option<ClientEvent>> {
+        if self.reconnect.is_none() {
+            return Err(e);
+        }
+
+        warn!("Lost connection to {}: {}", self.url, e);
+
+        self.sink = None;
+        self.disconnected = true;
+
+        Ok(Some(ClientEvent::Disconnected(e)))
+    }
+
+    async fn reconnect_impl(&mut self) -> ClientResult<ClientEvent> {
+        let policy = self.reconnect.unwrap_or_default();
+        let mut attempts = 0;
+        // Every attempt resets the world, so the keys that
+        // were held when the connection dropped have to be
+        // taken before the first one.
+        let keys = self.world.keys;
+
+        loop {
+            let backoff = policy.backoff(attempts);
+            r#await!(tokio::timer::Delay::new(Instant::now() + backoff))?;
+
+            attempts += 1;
+
+            match r#await!(self.try_reconnect(keys)) {
+                Ok(()) => break,
+                Err(e) => {
+                    if !policy.should_retry(attempts) {
+                        error!(
+                            "Giving up on reconnecting to {} after {} attempts",
+                            self.url, attempts
+                        );
+                        return Err(e);
+                    }
+
+                    warn!(
+                        "Reconnect attempt {} to {} failed: {}",
+                        attempts, self.url, e
+                    );
+                }
+            }
+        }
+
+        self.disconnected = false;
+        info!("Reconnected to {}", self.url);
+
+        Ok(ClientEvent::Reconnected)
+    }
+
+    async fn try_reconnect(&mut self, keys: ClientKeyState) -> ClientResult<()> {
+        use airmash_protocol::client::Key;
+
+        let ws_stream = r#await!(Self::connect(self.url.clone(), self.insecure))?;
+        let (sink, stream) = Self::split_stream(ws_stream);
+
+        self.sink = Some(sink);
+        self.stream = stream;
+
+        let login = match self.login.clone() {
+            Some(login) => login,
+            // The bot never logged in in the first place
+            // so there's nothing more to restore.
+            None => return Ok(()),
+        };
+
+        let timeout = self.reconnect.unwrap_or_default().login_timeout;
+        let upgrade_strategy = self.world.upgrade_strategy;
+        self.world = World::default();
+        self.world.upgrade_strategy = upgrade_strategy;
+        self.pending.clear();
+
+        r#await!(self.send(login))?;
+
+        // Wait for the server to accept the login. World gets
+        // rebuilt from the login packet within packet_update.
+        let deadline = Instant::now() + timeout;
+        loop {
+            let item = match r#await!(self.stream.next()) {
+                Some(x) => x?,
+                None => return Err(ClientError::Disconnected),
+            };
+
+            match self.parse_item(item)? {
+                Some(ClientEvent::Packet(ServerPacket::Error(p))) => {
+                    return Err(ClientError::Server(p.error));
+                }
+                Some(ClientEvent::Packet(p)) => {
+                    r#await!(self.packet_update(&p))?;
+
+                    if let ServerPacket::Login(_) = p {
+                        break;
+                    }
+                }
+                Some(ClientEvent::Frame(now)) if now > deadline => {
+                    return Err(ClientError::Timeout);
+                }
+                _ => (),
+            }
+        }
+
+        for key in keys.pressed() {
+            let seq = self.world.key_seq;
+            self.world.key_seq += 1;
+            self.world.keys.set(key, true);
+
+            r#await!(self.send(Key {
+                key,
+                seq,
+                state: true
+            }))?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Constructors
 impl ClientBase {
-    fn new_internal(ws_stream: WebSocketStream) -> Self {
+    fn split_stream(ws_stream: WebSocketStream) -> (ClientSink, ClientStream) {
         let (sink, stream) = ws_stream.split();
 
         let stream1 = stream
             .map_err(ClientError::from as FromFn<_, _>)
-            .and_then(parse_packet as ParsePacketFn)
-            .filter_map(id as fn(_) -> _);
+            .map(StreamItem::Message as MessageFn)
+            .chain(futures::stream::once(Ok(StreamItem::Closed)));
         let stream2 = Interval::new(Instant::now(), TICKER_TIME)
             .map_err(ClientError::from as FromFn<_, _>)
-            .map(parse_time as ParseTimeFn);
+            .map(StreamItem::Frame as TimeFn);
+
+        (sink, stream1.select(stream2).fuse())
+    }
+    fn new_internal(url: Url, insecure: bool, ws_stream: WebSocketStream) -> Self {
+        let (sink, stream) = Self::split_stream(ws_stream);
 
         Self {
             world: World::default(),
             sink: Some(sink),
-            stream: stream1.select(stream2).fuse(),
+            stream,
+            pending: VecDeque::new(),
+
+            url,
+            insecure,
+            login: None,
+            reconnect: None,
+            disconnected: false,
+            recorder: None,
         }
     }
     async fn from_tls_stream(
         url: Url,
         stream: tokio_tls::TlsStream<tokio::net::TcpStream>,
-    ) -> Result<Self, ClientError> {
+    ) -> Result<WebSocketStream, ClientError> {
         use tokio_tungstenite::client_async;
         use tokio_tungstenite::stream::Stream;
 
         let (ws_stream, _) = r#await!(client_async(url, Stream::Tls(stream)))?;
 
-        Ok(Self::new_internal(ws_stream))
+        Ok(ws_stream)
+    }
+    async fn connect(url: Url, insecure: bool) -> Result<WebSocketStream, ClientError> {
+        if insecure {
+            let stream = r#await!(connect_insecure(&url))?;
+
+            r#await!(Self::from_tls_stream(url, stream))
+        } else {
+            let (ws_stream, _) = r#await!(connect_async(url))?;
+
+            Ok(ws_stream)
+        }
     }
 
     pub async fn new(url: Url) -> Result<Self, ClientError> {
-        let (ws_stream, _) = r#await!(connect_async(url))?;
+        let ws_stream = r#await!(Self::connect(url.clone(), false))?;
 
-        Ok(Self::new_internal(ws_stream))
+        Ok(Self::new_internal(url, false, ws_stream))
     }
     pub async fn new_insecure(url: Url) -> Result<Self, ClientError> {
-        let stream = r#await!(connect_insecure(&url))?;
+        let ws_stream = r#await!(Self::connect(url.clone(), true))?;
 
-        r#await!(Self::from_tls_stream(url, stream))
+        Ok(Self::new_internal(url, true, ws_stream))
     }
 }
 
@@ -193,10 +494,6 @@ impl DerefMut for ClientBase {
     }
 }
 
-fn id<T>(v: T) -> T {
-    v
-}
-
 fn parse_packet(msg: Message) -> Result<Option<ClientEvent>, ClientError> {
     let buf = match msg {
         Message::Binary(buf) => buf,
@@ -243,7 +540,3 @@ async fn connect_insecure(
 
     Ok(stream)
 }
-
-fn parse_time(inst: Instant) -> ClientEvent {
-    ClientEvent::Frame(inst)
-}
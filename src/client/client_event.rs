@@ -1,7 +1,21 @@
+use crate::{ClientError, GameEvent};
 use protocol::ServerPacket;
 use std::time::Instant;
 
 pub enum ClientEvent {
     Frame(Instant),
     Packet(ServerPacket),
+    /// A high-level event derived from the previous
+    /// packet. These are always delivered directly
+    /// after the packet that caused them.
+    Game(GameEvent),
+    /// The connection to the server was lost. The
+    /// client will attempt to reconnect the next
+    /// time it is polled.
+    Disconnected(ClientError),
+    /// The client has reconnected and logged in
+    /// again after a [`Disconnected`] event.
+    ///
+    /// [`Disconnected`]: ClientEvent::Disconnected
+    Reconnected,
 }
@@ -0,0 +1,230 @@
+use std::f32::consts::PI;
+use std::time::{Duration, Instant};
+
+use airmash_protocol::*;
+
+use super::*;
+use crate::config::MissileInfo;
+use crate::consts::{BASE_DIR, CONFIG};
+use crate::game::World;
+
+/// Number of bisection steps used when solving for
+/// the intercept time. Each one halves the error.
+const INTERCEPT_ITERATIONS: usize = 20;
+
+/// The distance (in units) that a missile fired with
+/// the given initial speed will have travelled after
+/// `t` frames.
+fn missile_distance(info: &MissileInfo, initial: f32, max_speed: f32, t: f32) -> f32 {
+    let accel = info.accel.inner();
+    let accel_time = ((max_speed - initial) / accel).max(0.0);
+
+    if t <= accel_time {
+        initial * t + 0.5 * accel * t * t
+    } else {
+        initial * accel_time + 0.5 * accel * accel_time * accel_time + max_speed * (t - accel_time)
+    }
+}
+
+/// See [`ImplClient::can_fire`].
+pub(crate) fn can_fire(world: &World) -> bool {
+    let me = world.get_me();
+    let info = &CONFIG.planes[me.plane];
+
+    let cooled_down = match world.me.last_fire {
+        Some(last) => last.elapsed() >= info.fire_delay,
+        None => true,
+    };
+
+    cooled_down && me.energy >= info.fire_energy
+}
+
+/// See [`ImplClient::lead_angle`].
+pub(crate) fn lead_angle(world: &World, player: u16) -> Option<Rotation> {
+    let me = world.get_me();
+    let target = world.players.get(&player)?;
+
+    let plane = &CONFIG.planes[me.plane];
+    let info = CONFIG.mobs[plane.missile_type].missile?;
+
+    let latency = Time::from(Duration::from_millis(world.ping as u64)).inner();
+    let tvel = (target.vel.x.inner(), target.vel.y.inner());
+    let rel = (
+        target.pos.x.inner() + tvel.0 * latency - me.pos.x.inner(),
+        target.pos.y.inner() + tvel.1 * latency - me.pos.y.inner(),
+    );
+
+    // The missile inherits some of our speed along the
+    // direction that we're facing. Our heading will be
+    // different once we've turned, but this is close
+    // enough for anything within the missile's range.
+    let dir = (me.rot.inner().sin(), -me.rot.inner().cos());
+    let forward = me.vel.x.inner() * dir.0 + me.vel.y.inner() * dir.1;
+    let initial = info.base_speed.inner() + info.speed_factor * forward.max(0.0);
+    let max_speed = info.max_speed.inner()
+        * CONFIG.upgrades.missile.factor[world.me.upgrades.missile as usize]
+        + info.speed_factor * forward.max(0.0);
+
+    let offset = plane.missile_offset.inner();
+    let miss = |t: f32| {
+        let x = rel.0 + tvel.0 * t;
+        let y = rel.1 + tvel.1 * t;
+
+        missile_distance(&info, initial, max_speed, t) + offset - (x * x + y * y).sqrt()
+    };
+
+    // The longest the missile can fly for before
+    // running out of range.
+    let (mut lo, mut hi) = (0.0, 1.0);
+    while missile_distance(&info, initial, max_speed, hi) < info.distance.inner() {
+        hi *= 2.0;
+    }
+    if miss(hi) < 0.0 {
+        return None;
+    }
+
+    for _ in 0..INTERCEPT_ITERATIONS {
+        let mid = 0.5 * (lo + hi);
+
+        if miss(mid) < 0.0 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    let aim: Vector2<f32> = Vector2::new(rel.0 + tvel.0 * hi, rel.1 + tvel.1 * hi);
+    let aim = aim.normalized();
+    let mut angle = Vector2::dot(aim, BASE_DIR).acos();
+
+    if aim.x < 0.0.into() {
+        angle = 2.0 * PI - angle;
+    }
+
+    Some(angle.into())
+}
+
+impl<T: Client> ImplClient<T> {
+    /// Whether our plane has enough energy to fire and
+    /// the fire delay since the last shot has passed.
+    pub fn can_fire(&self) -> bool {
+        can_fire(self.world())
+    }
+
+    /// Fire a single shot in whatever direction we are
+    /// currently facing.
+    ///
+    /// Returns `false` without sending anything if
+    /// [`can_fire`](ImplClient::can_fire) says that the
+    /// shot wouldn't happen anyway.
+    pub async fn fire(&mut self) -> ClientResult<bool> {
+        if !self.can_fire() {
+            return Ok(false);
+        }
+
+        r#await!(self.press_key(KeyCode::Fire))?;
+        // The server will set this too once it confirms
+        // the shot, but we don't want to fire twice in
+        // the meantime.
+        self.world_mut().me.last_fire = Some(Instant::now());
+
+        // Hold the key for a frame so that the server
+        // actually sees it.
+        r#await!(self.next_frame())?;
+        r#await!(self.release_key(KeyCode::Fire))?;
+
+        Ok(true)
+    }
+
+    /// The heading we need to fire at to hit the given
+    /// player, assuming they keep their current velocity.
+    ///
+    /// The target position is extrapolated by our ping
+    /// since both their position and our shot are half
+    /// a round trip behind the server. Returns `None` if
+    /// the player is unknown or out of range.
+    pub fn lead_angle(&self, player: u16) -> Option<Rotation> {
+        lead_angle(self.world(), player)
+    }
+
+    /// Turn to lead the given player and fire at them.
+    ///
+    /// Returns whether a shot was fired. Nothing is sent
+    /// if the player is out of range or we can't fire.
+    pub async fn fire_at(&mut self, player: u16) -> ClientResult<bool> {
+        if self.lead_angle(player).is_none() {
+            return Ok(false);
+        }
+
+        // Wait until we're actually able to fire before
+        // turning so that the angle is still good when we
+        // do.
+        while !self.can_fire() {
+            if r#await!(self.next_frame())?.is_none() {
+                return Ok(false);
+            }
+        }
+
+        let angle = match self.lead_angle(player) {
+            Some(angle) => angle,
+            None => return Ok(false),
+        };
+        r#await!(self.turn_to(angle))?;
+
+        r#await!(self.fire())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Player;
+
+    /// A world where we sit at the origin facing up and
+    /// player 2 is `distance` units straight ahead.
+    fn world(distance: f32, vel: Velocity) -> World {
+        let mut world = World::default();
+        world.me.id = 1;
+        world.players.insert(
+            1,
+            Player {
+                id: 1,
+                ..Default::default()
+            },
+        );
+        world.players.insert(
+            2,
+            Player {
+                id: 2,
+                pos: Position::new(0.0, -distance),
+                vel,
+                ..Default::default()
+            },
+        );
+        world
+    }
+
+    #[test]
+    fn stationary_target_ahead() {
+        let world = world(300.0, Velocity::new(0.0, 0.0));
+        let angle = lead_angle(&world, 2).unwrap();
+
+        assert!(angle.inner().abs() < 1e-3);
+    }
+
+    #[test]
+    fn leads_moving_target() {
+        let world = world(300.0, Velocity::new(5.0, 0.0));
+        let angle = lead_angle(&world, 2).unwrap().inner();
+
+        assert!(angle > 0.0 && angle < PI / 2.0);
+    }
+
+    #[test]
+    fn target_out_of_range() {
+        let world = world(5000.0, Velocity::new(0.0, 0.0));
+
+        assert!(lead_angle(&world, 2).is_none());
+        assert!(lead_angle(&world, 3).is_none());
+    }
+}
@@ -0,0 +1,162 @@
+use std::time::{Duration, Instant};
+
+use airmash_protocol::*;
+
+use super::*;
+use crate::game::{BLUE_TEAM, RED_TEAM};
+
+/// How long to wait for the server to respond to a
+/// command before giving up.
+pub(crate) const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl<T: Client> ImplClient<T> {
+    /// Send a [`Command`] packet.
+    ///
+    /// [`Command`]: protocol::client::Command
+    pub async fn send_command(&mut self, com: &str, data: String) -> ClientResult<()> {
+        r#await!(self.send(client::Command {
+            com: com.to_owned(),
+            data,
+        }))
+    }
+
+    /// Process events until `matcher` picks out the reply
+    /// to a request that we have just sent.
+    ///
+    /// Fails with [`ClientError::Server`] if the server
+    /// sends an error packet first and with
+    /// [`ClientError::Timeout`] if nothing matches within
+    /// `timeout`.
+    pub async fn wait_for_reply<F, R>(&mut self, timeout: Duration, matcher: F) -> ClientResult<R>
+    where
+        F: FnMut(&ServerPacket) -> Option<R> + Send,
+        R: Send,
+    {
+        r#await!(self.wait_for_reply_or_error(timeout, |_| true, matcher))
+    }
+
+    /// The same as [`wait_for_reply`], except that only
+    /// errors accepted by `is_error` cause it to fail. Any
+    /// other error packets are assumed to be about
+    /// something else and are skipped.
+    ///
+    /// [`wait_for_reply`]: ImplClient::wait_for_reply
+    pub async fn wait_for_reply_or_error<F, R>(
+        &mut self,
+        timeout: Duration,
+        is_error: fn(ErrorType) -> bool,
+        mut matcher: F,
+    ) -> ClientResult<R>
+    where
+        F: FnMut(&ServerPacket) -> Option<R> + Send,
+        R: Send,
+    {
+        use self::ClientEvent::*;
+
+        let deadline = Instant::now() + timeout;
+        while let Some(evt) = r#await!(self.next())? {
+            match evt {
+                Packet(ServerPacket::Error(p)) => {
+                    if is_error(p.error) {
+                        return Err(ClientError::Server(p.error));
+                    }
+                }
+                Packet(ref p) => {
+                    if let Some(reply) = matcher(p) {
+                        return Ok(reply);
+                    }
+                }
+                Frame(now) if now > deadline => return Err(ClientError::Timeout),
+                _ => (),
+            }
+        }
+
+        Err(ClientError::Disconnected)
+    }
+
+    /// Respawn, possibly in a different plane.
+    ///
+    /// Waits for the server to send our `PlayerRespawn`.
+    pub async fn respawn(&mut self, plane: PlaneType) -> ClientResult<()> {
+        let me = self.world().me.id;
+
+        r#await!(self.send_command("respawn", (plane as u8).to_string()))?;
+        r#await!(self.wait_for_reply(COMMAND_TIMEOUT, |p| match p {
+            ServerPacket::PlayerRespawn(p) if p.id.0 == me => Some(()),
+            _ => None,
+        }))
+    }
+
+    /// Go into spectate mode. If `player` is given then
+    /// we will watch that player.
+    ///
+    /// Returns the id of the player being watched, as
+    /// confirmed by a `GameSpectate` packet.
+    pub async fn spectate(&mut self, player: Option<u16>) -> ClientResult<u16> {
+        let data = match player {
+            Some(id) => id.to_string(),
+            // Tells the server to pick someone
+            None => "-1".to_owned(),
+        };
+
+        r#await!(self.send_command("spectate", data))?;
+        r#await!(self.wait_for_reply(COMMAND_TIMEOUT, |p| match p {
+            ServerPacket::GameSpectate(p) => Some(p.id.0),
+            _ => None,
+        }))
+    }
+
+    /// Change our flag to the given country code.
+    ///
+    /// Waits for the server to send our `PlayerFlag`.
+    pub async fn set_flag(&mut self, flag: &str) -> ClientResult<()> {
+        let me = self.world().me.id;
+
+        r#await!(self.send_command("flag", flag.to_owned()))?;
+        r#await!(self.wait_for_reply(COMMAND_TIMEOUT, |p| match p {
+            ServerPacket::PlayerFlag(p) if p.id.0 == me => Some(()),
+            _ => None,
+        }))
+    }
+
+    /// Drop the flag that we are carrying in a CTF game.
+    ///
+    /// Waits for the server to send a `GameFlag` showing
+    /// that we are no longer carrying it. Fails straight
+    /// away if we aren't carrying a flag.
+    pub async fn ctf_drop(&mut self) -> ClientResult<()> {
+        let me = self.world().me.id;
+        let carrying = |team| self.world().flag_carrier(team) == Some(me);
+        let team = if carrying(BLUE_TEAM) {
+            BLUE_TEAM
+        } else if carrying(RED_TEAM) {
+            RED_TEAM
+        } else {
+            return Err(ClientError::NotCarryingFlag);
+        };
+
+        r#await!(self.send_command("drop", String::new()))?;
+        r#await!(self.wait_for_reply(COMMAND_TIMEOUT, |p| match p {
+            ServerPacket::GameFlag(p) if p.flag.0 == team && p.id.map(|id| id.0) != Some(me) => {
+                Some(())
+            }
+            _ => None,
+        }))
+    }
+
+    /// Switch to the other team in a CTF game.
+    ///
+    /// Returns our new team once the server sends a
+    /// `PlayerReteam` that includes us.
+    pub async fn ctf_switch(&mut self) -> ClientResult<Team> {
+        let me = self.world().me.id;
+
+        r#await!(self.send_command("switch", String::new()))?;
+        r#await!(self.wait_for_reply(COMMAND_TIMEOUT, |p| match p {
+            ServerPacket::PlayerReteam(p) => {
+                p.players.iter().find(|x| x.id.0 == me).map(|x| x.team)
+            }
+            _ => None,
+        }))
+    }
+}
@@ -1,9 +1,11 @@
+use airmash_protocol::{ErrorType, PlaneType, UpgradeType};
 use airmash_protocol_v5::{DeserializeError, SerializeError};
 use tokio::timer::Error as TimerError;
 use tungstenite::Error as WsError;
 
 use std::error::Error;
 use std::fmt::{Display, Error as FmtError, Formatter};
+use std::io::Error as IoError;
 
 pub type ClientResult<T> = Result<T, ClientError>;
 
@@ -13,7 +15,29 @@ pub enum ClientError {
     Serialize(SerializeError),
     Deserialize(DeserializeError),
     Timer(TimerError),
+    Io(IoError),
     InvalidWsFrame(String),
+    Disconnected,
+    /// The server rejected a request with an error packet.
+    Server(ErrorType),
+    /// The server didn't reply to a request in time.
+    Timeout,
+    NotEnoughUpgrades {
+        available: u16,
+        cost: u16,
+    },
+    /// The upgrade is already at its maximum level or
+    /// isn't a real upgrade.
+    InvalidUpgrade(UpgradeType),
+    /// The requested action needs a different plane.
+    WrongPlane {
+        expected: PlaneType,
+        actual: PlaneType,
+    },
+    /// We tried to drop the flag without carrying one.
+    NotCarryingFlag,
+    /// The server replied to a command with an error.
+    CommandFailed(String),
     Custom(Box<Error + Send + 'static>),
 }
 
@@ -41,6 +65,12 @@ impl From<TimerError> for ClientError {
     }
 }
 
+impl From<IoError> for ClientError {
+    fn from(e: IoError) -> Self {
+        ClientError::Io(e)
+    }
+}
+
 impl Display for ClientError {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), FmtError> {
         use self::ClientError::*;
@@ -49,7 +79,24 @@ impl Display for ClientError {
             Serialize(e) => write!(fmt, "Serialize({})", e),
             Deserialize(e) => write!(fmt, "Deserialize({})", e),
             Timer(e) => write!(fmt, "Timer({})", e),
+            Io(e) => write!(fmt, "Io({})", e),
             InvalidWsFrame(desc) => write!(fmt, "InvalidWsFrame({})", desc),
+            Disconnected => write!(fmt, "Disconnected"),
+            Server(e) => write!(fmt, "Server({:?})", e),
+            Timeout => write!(fmt, "Timeout"),
+            NotEnoughUpgrades { available, cost } => write!(
+                fmt,
+                "NotEnoughUpgrades(available: {}, cost: {})",
+                available, cost
+            ),
+            InvalidUpgrade(ty) => write!(fmt, "InvalidUpgrade({:?})", ty),
+            WrongPlane { expected, actual } => write!(
+                fmt,
+                "WrongPlane(expected: {:?}, actual: {:?})",
+                expected, actual
+            ),
+            NotCarryingFlag => write!(fmt, "NotCarryingFlag"),
+            CommandFailed(msg) => write!(fmt, "CommandFailed({})", msg),
             Custom(e) => write!(fmt, "Custom({})", e),
         }
     }
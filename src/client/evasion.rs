@@ -0,0 +1,275 @@
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use airmash_protocol::*;
+
+use super::*;
+use crate::consts::CONFIG;
+use crate::game::{closest_approach, ClientKeyState, Mob, Player, World};
+
+/// How far ahead candidate inputs are simulated.
+const EVASION_HORIZON: Duration = Duration::from_secs(1);
+/// Missiles further away than this can't reach us
+/// within the horizon and are skipped.
+const EVASION_RANGE: f32 = 1500.0;
+/// The most missiles that each candidate is simulated
+/// against. Every candidate costs a full simulation per
+/// missile so this keeps busy fights cheap.
+const MAX_EVASION_MISSILES: usize = 6;
+
+/// The keys that evasion manages. Fire is left alone
+/// so that a bot can keep shooting while dodging.
+const EVASION_KEYS: [KeyCode; 5] = [
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Special,
+];
+
+/// All the key combinations worth trying for a plane.
+fn candidates(player: &Player) -> Vec<ClientKeyState> {
+    let info = &CONFIG.planes[player.plane];
+    let mut specials = vec![false];
+
+    match player.plane {
+        // Boosting
+        PlaneType::Predator if player.energy >= info.special_energy => specials.push(true),
+        // Strafing
+        PlaneType::Mohawk => specials.push(true),
+        _ => (),
+    }
+
+    let mut keys = vec![];
+    for &special in specials.iter() {
+        for &(left, right) in [(false, false), (true, false), (false, true)].iter() {
+            for &(up, down) in [(false, false), (true, false), (false, true)].iter() {
+                keys.push(ClientKeyState {
+                    up,
+                    down,
+                    left,
+                    right,
+                    special,
+                    fire: false,
+                });
+            }
+        }
+    }
+
+    keys
+}
+
+/// Pick the keys that maximise the distance by which
+/// every incoming missile misses our plane over the
+/// next second.
+///
+/// Each candidate is simulated with the same physics
+/// that `World` uses. Returns `None` if nothing is
+/// expected to hit us with the keys that we are
+/// currently holding.
+pub fn evasion_keys(world: &World) -> Option<ClientKeyState> {
+    let me = world.get_me();
+    let frames = Time::from(EVASION_HORIZON).inner().ceil() as u32;
+
+    let threats = world.incoming_threats(me.id, EVASION_HORIZON);
+    if threats.is_empty() {
+        return None;
+    }
+
+    let mut missiles: Vec<_> = world
+        .mobs
+        .values()
+        .filter(|mob| mob.missile() && !world.is_friendly(mob, me))
+        .filter(|mob| (mob.pos - me.pos).length().inner() < EVASION_RANGE)
+        .cloned()
+        .collect();
+
+    // Dodging one missile could put us in the path of
+    // another, so after the ones that are going to hit
+    // us keep the closest of the rest.
+    let is_threat = |mob: &Mob| threats.iter().any(|t| t.mob == mob.id);
+    let distance = |mob: &Mob| (mob.pos - me.pos).length().inner();
+    missiles.sort_by(|a, b| {
+        is_threat(b)
+            .cmp(&is_threat(a))
+            .then(distance(a).partial_cmp(&distance(b)).unwrap_or(Ordering::Equal))
+    });
+    missiles.truncate(MAX_EVASION_MISSILES);
+
+    // The worst miss distance across all missiles. This
+    // stops early once it drops to `floor` since the
+    // candidate can't win at that point.
+    let score = |keys: &ClientKeyState, floor: f32| {
+        let mut player = me.clone();
+        player.keystate.up = keys.up;
+        player.keystate.down = keys.down;
+        player.keystate.left = keys.left;
+        player.keystate.right = keys.right;
+        player.keystate.boost = keys.special && me.plane == PlaneType::Predator;
+        player.keystate.strafe = keys.special && me.plane == PlaneType::Mohawk;
+
+        let mut worst = std::f32::INFINITY;
+        for mob in missiles.iter() {
+            worst = worst.min(closest_approach(player.clone(), *mob, frames).gap);
+
+            if worst <= floor {
+                break;
+            }
+        }
+
+        worst
+    };
+
+    // Start with the keys we already have so that ties
+    // don't cause us to send useless key packets.
+    let mut current = world.keys;
+    current.fire = false;
+
+    let mut best = (current, score(&current, std::f32::NEG_INFINITY));
+    for keys in candidates(me) {
+        let value = score(&keys, best.1);
+
+        if value > best.1 {
+            best = (keys, value);
+        }
+    }
+
+    Some(best.0)
+}
+
+impl<T: Client> ImplClient<T> {
+    /// Take a single step of missile evasion.
+    ///
+    /// If any missile is expected to hit us this presses
+    /// and releases keys according to [`evasion_keys`].
+    /// Only keys that need to change are sent and this
+    /// never waits for any events, so it can be called
+    /// on every event alongside whatever else the bot is
+    /// doing.
+    ///
+    /// Once there is nothing left to dodge, any keys that
+    /// evasion changed are put back the way they were.
+    ///
+    /// Returns whether we are currently dodging anything.
+    pub async fn evade(&mut self) -> ClientResult<bool> {
+        let keys = evasion_keys(self.world());
+        let mut evading = self.world().me.evading;
+        let mut previous = self.world().me.pre_evasion;
+
+        for &key in EVASION_KEYS.iter() {
+            let current = self.world().keys.get(key);
+            // What the bot itself last wanted for this key
+            let own = if evading.get(key) {
+                previous.get(key)
+            } else {
+                current
+            };
+            let state = match keys {
+                Some(keys) => keys.get(key),
+                None => own,
+            };
+
+            if current != state {
+                r#await!(self.send_key(key, state))?;
+            }
+            evading.set(key, state != own);
+            previous.set(key, own);
+        }
+
+        self.world_mut().me.evading = evading;
+        self.world_mut().me.pre_evasion = previous;
+
+        Ok(keys.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::future::block_on;
+    use crate::ScriptedClient;
+    use tokio::r#await;
+
+    fn player(plane: PlaneType, energy: f32) -> Player {
+        Player {
+            id: 1,
+            plane,
+            pos: Position::new(-10000.0, 0.0),
+            energy: Energy::new(energy),
+            ..Default::default()
+        }
+    }
+
+    /// A world where a missile is flying straight at
+    /// our plane from below.
+    fn world() -> World {
+        let mut world = World::default();
+        world.me.id = 1;
+        world.players.insert(1, player(PlaneType::Predator, 0.0));
+        world.mobs.insert(
+            2,
+            Mob {
+                accel: Accel::default(),
+                vel: Velocity::new(0.0, -6.0),
+                pos: Position::new(-10000.0, 400.0),
+                max_speed: Speed::new(6.0),
+                owner: None,
+                id: 2,
+                ty: MobType::PredatorMissile,
+            },
+        );
+        world
+    }
+
+    #[test]
+    fn candidates_for_each_plane() {
+        assert_eq!(candidates(&player(PlaneType::Predator, 0.0)).len(), 9);
+        assert_eq!(candidates(&player(PlaneType::Predator, 1.0)).len(), 18);
+        assert_eq!(candidates(&player(PlaneType::Mohawk, 0.0)).len(), 18);
+        assert_eq!(candidates(&player(PlaneType::Goliath, 1.0)).len(), 9);
+
+        for keys in candidates(&player(PlaneType::Mohawk, 0.0)) {
+            assert!(!keys.fire);
+            assert!(!(keys.left && keys.right));
+            assert!(!(keys.up && keys.down));
+        }
+    }
+
+    #[test]
+    fn nothing_to_dodge() {
+        let mut world = world();
+        world.mobs.clear();
+
+        assert!(evasion_keys(&world).is_none());
+    }
+
+    #[test]
+    fn dodges_incoming_missile() {
+        let world = world();
+        assert!(!world.incoming_threats(1, EVASION_HORIZON).is_empty());
+
+        let keys = evasion_keys(&world).unwrap();
+        assert_ne!(keys, ClientKeyState::default());
+    }
+
+    #[test]
+    fn evade_restores_held_keys() {
+        let mut client = ScriptedClient::new();
+        client.world = world();
+        // Held by the bot before the missile showed up
+        client.world.keys.up = true;
+
+        let client = block_on(async move {
+            assert!(r#await!(client.evade()).unwrap());
+
+            client.world.mobs.clear();
+            assert!(!r#await!(client.evade()).unwrap());
+            client
+        });
+
+        let mut expected = ClientKeyState::default();
+        expected.up = true;
+        assert_eq!(client.world.keys, expected);
+        assert_eq!(client.world.me.evading, ClientKeyState::default());
+    }
+}
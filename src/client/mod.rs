@@ -1,10 +1,29 @@
+#[cfg(feature = "admin")]
+mod admin;
 mod client;
 mod client_base;
 mod client_event;
+mod combat;
+mod commands;
 mod error;
+mod evasion;
 mod pathfinding;
+mod reconnect;
+mod scripted;
+mod special;
+mod steering;
+mod upgrades;
 
+#[cfg(feature = "admin")]
+pub use self::admin::{AdminGameState, AdminReply};
 pub use self::client::{Client, ClientFuture, ImplClient};
 pub use self::client_base::ClientBase;
 pub use self::client_event::ClientEvent;
 pub use self::error::{ClientError, ClientResult};
+pub use self::evasion::evasion_keys;
+pub use self::reconnect::ReconnectPolicy;
+pub use self::scripted::ScriptedClient;
+
+pub(crate) use self::combat::{can_fire, lead_angle};
+pub(crate) use self::steering::{angle_to, heading_error, turn_key};
+pub(crate) use self::upgrades::{upgrade_settled, UPGRADE_TIMEOUT};
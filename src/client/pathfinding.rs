@@ -1,27 +1,13 @@
-use std::f32::consts::PI;
 use std::time::{Duration, Instant};
-//use hashbrown::HashSet;
-//use std::collections::BinaryHeap;
 use airmash_protocol::*;
 
+use super::steering::{angle_to, heading_error};
 use super::*;
-use crate::consts::BASE_DIR;
-//use crate::map::MAP;
+use crate::map::{find_path, line_of_sight};
 
 use protocol::Position;
 
 impl<T: Client> ImplClient<T> {
-    fn calc_angle(&mut self, pos: Position) -> f32 {
-        let rel = (pos - self.world().get_me().pos).normalized();
-        let mut angle = Vector2::dot(rel, BASE_DIR).acos();
-
-        if rel.x < 0.0.into() {
-            angle = 2.0 * PI - angle;
-        }
-
-        angle
-    }
-
     pub async fn run_straight_at(&mut self, pos: Position) -> ClientResult<()> {
         r#await!(self.point_at(pos))?;
         r#await!(self.press_key(KeyCode::Up))?;
@@ -29,10 +15,11 @@ impl<T: Client> ImplClient<T> {
         r#await!(self.wait(wait_duration))?;
 
         while let Some(_) = r#await!(self.next())? {
-            let dist = (pos - self.world().get_me().pos).length();
-            let angle = self.calc_angle(pos);
+            let mypos = self.world().get_me().pos;
+            let dist = (pos - mypos).length();
+            let error = heading_error(angle_to(mypos, pos), self.world().get_me().rot);
 
-            if angle > 1.0 {
+            if error.inner().abs() > 1.0 {
                 if dist.inner() < 500.0 {
                     r#await!(self.release_key(KeyCode::Up))?;
                 }
@@ -59,6 +46,37 @@ impl<T: Client> ImplClient<T> {
         r#await!(self.release_key(KeyCode::Up))
     }
 
+    /// Fly back into the BTR safe zone.
+    ///
+    /// This heads towards a point halfway between the
+    /// plane and the centre of the zone so that the bot
+    /// ends up comfortably inside even while the zone
+    /// keeps shrinking. It returns immediately if there
+    /// is no firewall or the bot is already inside.
+    pub async fn return_to_safe_zone(&mut self) -> ClientResult<()> {
+        let firewall = match self.world().btr.firewall {
+            Some(firewall) => firewall,
+            None => return Ok(()),
+        };
+
+        let pos = self.world().get_me().pos;
+        if self.world().is_inside_safe_zone(pos) {
+            return Ok(());
+        }
+
+        let dx = pos.x.inner() - firewall.pos.x.inner();
+        let dy = pos.y.inner() - firewall.pos.y.inner();
+        let len = (dx * dx + dy * dy).sqrt().max(1.0);
+        let dist = firewall.radius * 0.5;
+
+        let target = Position::new(
+            firewall.pos.x.inner() + dx / len * dist,
+            firewall.pos.y.inner() + dy / len * dist,
+        );
+
+        r#await!(self.run_straight_at(target))
+    }
+
     pub async fn follow(&mut self, player: u16) -> ClientResult<()> {
         let mut pos;
         let mut prev = Instant::now();
@@ -79,7 +97,13 @@ impl<T: Client> ImplClient<T> {
                 prev = Instant::now();
             }
 
-            r#await!(self.point_at(pos))?;
+            // If the turn didn't converge then try again straight
+            // away instead of flying off in the wrong direction.
+            let error = r#await!(self.point_at(pos))?;
+            if error.inner().abs() > 0.1 {
+                continue;
+            }
+
             let wait_duration =
                 Duration::from_millis((self.world().ping * 2).min(1000).max(10) as u64);
             r#await!(self.wait(wait_duration))?;
@@ -87,4 +111,66 @@ impl<T: Client> ImplClient<T> {
 
         r#await!(self.release_key(KeyCode::Up))
     }
+
+    /// Fly to the given position while avoiding terrain.
+    ///
+    /// This plans a path around the terrain with
+    /// [`map::find_path`] and follows the waypoints, planning
+    /// again whenever the next waypoint can no longer be
+    /// reached in a straight line (e.g. after bouncing
+    /// off a hill or being repelled).
+    ///
+    /// Returns `false` if no path could be found.
+    ///
+    /// [`map::find_path`]: crate::map::find_path
+    pub async fn navigate_to(&mut self, pos: Position) -> ClientResult<bool> {
+        let mut path = match find_path(self.world().get_me().pos, pos) {
+            Some(path) => path,
+            None => return Ok(false),
+        };
+
+        r#await!(self.press_key(KeyCode::Up))?;
+
+        while let Some(_) = r#await!(self.next_frame())? {
+            let mypos = self.world().get_me().pos;
+
+            if (pos - mypos).length().inner() < ARRIVE_DISTANCE {
+                break;
+            }
+
+            while path.len() > 1 && (path[0] - mypos).length().inner() < WAYPOINT_RADIUS {
+                path.remove(0);
+            }
+
+            if !line_of_sight(mypos, path[0]) {
+                path = match find_path(mypos, pos) {
+                    Some(path) => path,
+                    None => {
+                        r#await!(self.release_key(KeyCode::Up))?;
+                        return Ok(false);
+                    }
+                };
+            }
+
+            let angle = angle_to(mypos, path[0]);
+            let error = heading_error(angle, self.world().get_me().rot);
+            if error.inner().abs() > WAYPOINT_TOLERANCE {
+                r#await!(self.point_at(path[0]))?;
+            }
+        }
+
+        r#await!(self.release_key(KeyCode::Up))?;
+
+        Ok(true)
+    }
 }
+
+/// How close we need to get to the final position
+/// before `navigate_to` is done.
+const ARRIVE_DISTANCE: f32 = 100.0;
+/// How close we need to get to a waypoint before
+/// heading towards the next one.
+const WAYPOINT_RADIUS: f32 = 150.0;
+/// How far off of the next waypoint our heading can
+/// be before we turn towards it again.
+const WAYPOINT_TOLERANCE: f32 = 0.15;
@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+/// Controls how a [`ClientBase`] reconnects when
+/// the websocket connection to the server drops.
+///
+/// Reconnecting is opt-in; a client without a
+/// policy will return the connection error as
+/// it always has.
+///
+/// [`ClientBase`]: crate::ClientBase
+#[derive(Copy, Clone, Debug)]
+pub struct ReconnectPolicy {
+    /// Delay before the first reconnect attempt.
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between attempts.
+    /// The delay doubles after every failed attempt
+    /// until it reaches this value.
+    pub max_backoff: Duration,
+    /// The number of attempts to make before giving
+    /// up. `None` will retry forever.
+    pub max_attempts: Option<u32>,
+    /// How long to wait for the server to accept the
+    /// login after reconnecting before counting the
+    /// attempt as failed.
+    pub login_timeout: Duration,
+}
+
+impl ReconnectPolicy {
+    /// The delay to wait before the given (zero-based)
+    /// reconnect attempt.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt).unwrap_or(u32::max_value());
+
+        self.initial_backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// Whether another attempt should be made after
+    /// `attempts` attempts have already failed.
+    pub fn should_retry(&self, attempts: u32) -> bool {
+        match self.max_attempts {
+            Some(max) => attempts < max,
+            None => true,
+        }
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_attempts: Some(10),
+            login_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let policy = ReconnectPolicy::default();
+
+        assert_eq!(policy.backoff(0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(5), Duration::from_secs(16));
+        assert_eq!(policy.backoff(6), Duration::from_secs(30));
+        assert_eq!(policy.backoff(100), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn retries_until_max_attempts() {
+        let mut policy = ReconnectPolicy {
+            max_attempts: Some(3),
+            ..Default::default()
+        };
+
+        assert!(policy.should_retry(2));
+        assert!(!policy.should_retry(3));
+
+        policy.max_attempts = None;
+        assert!(policy.should_retry(1000));
+    }
+}
@@ -0,0 +1,365 @@
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, Instant};
+
+use airmash_protocol::*;
+use airmash_protocol_v5::ProtocolV5;
+
+use crate::game::World;
+use crate::ClientEvent;
+use crate::{Client, ClientError, ClientFuture, ImplClient};
+
+/// A client that plays back a fixed queue of events
+/// instead of talking to a server.
+///
+/// Events are applied to the world in the same way
+/// that [`ClientBase`] applies them, and every packet
+/// sent by the bot is decoded and kept so that it can
+/// be inspected afterwards. Once the queue runs out
+/// the client behaves as if the connection closed.
+///
+/// [`ClientBase`]: crate::ClientBase
+#[derive(Default)]
+pub struct ScriptedClient {
+    pub world: World,
+    events: VecDeque<ClientEvent>,
+    pending: VecDeque<ClientEvent>,
+    sent: Vec<ClientPacket>,
+}
+
+impl Client for ScriptedClient {
+    fn world(&self) -> &World {
+        &self.world
+    }
+    fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    fn _next<'a>(&'a mut self) -> ClientFuture<'a, Option<ClientEvent>> {
+        Box::new(self._next_impl())
+    }
+
+    fn _send_buf<'a>(&'a mut self, buf: Vec<u8>) -> ClientFuture<'a, ()> {
+        Box::new(self._send_buf_impl(buf))
+    }
+}
+
+/// async trait backing functions
+impl ScriptedClient {
+    async fn _next_impl(&mut self) -> Result<Option<ClientEvent>, ClientError> {
+        Ok(self.next_event())
+    }
+
+    async fn _send_buf_impl(&mut self, buf: Vec<u8>) -> Result<(), ClientError> {
+        let packet = ProtocolV5 {}.deserialize_client(&buf)?;
+        self.sent.push(packet);
+
+        Ok(())
+    }
+}
+
+impl ScriptedClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue an arbitrary event.
+    pub fn push_event(&mut self, evt: ClientEvent) {
+        self.events.push_back(evt);
+    }
+
+    /// Queue a packet from the server.
+    pub fn push_packet<P>(&mut self, packet: P)
+    where
+        P: Into<ServerPacket>,
+    {
+        self.push_event(ClientEvent::Packet(packet.into()));
+    }
+
+    /// Queue a single frame tick.
+    pub fn push_frame(&mut self, now: Instant) {
+        self.push_event(ClientEvent::Frame(now));
+    }
+
+    /// Queue `count` frame ticks spaced `interval` apart,
+    /// with the first one occurring at `start`.
+    pub fn push_frames(&mut self, start: Instant, interval: Duration, count: usize) {
+        for i in 0..count {
+            self.push_frame(start + interval * i as u32);
+        }
+    }
+
+    /// The number of events that have yet to be processed.
+    pub fn remaining(&self) -> usize {
+        self.events.len()
+    }
+
+    /// All packets that the bot has sent so far.
+    pub fn sent(&self) -> &[ClientPacket] {
+        &self.sent
+    }
+
+    /// Take all packets that the bot has sent so far,
+    /// leaving the list of sent packets empty.
+    pub fn take_sent(&mut self) -> Vec<ClientPacket> {
+        std::mem::replace(&mut self.sent, vec![])
+    }
+
+    fn next_event(&mut self) -> Option<ClientEvent> {
+        use self::ClientEvent::*;
+
+        if let Some(evt) = self.pending.pop_front() {
+            return Some(evt);
+        }
+
+        let evt = self.events.pop_front()?;
+
+        match &evt {
+            Packet(p) => {
+                let events = self.world.handle_packet(p);
+                self.pending.extend(events.into_iter().map(Game));
+            }
+            Frame(now) => self.world.update(*now),
+            Disconnected(_) | Reconnected | Game(_) => (),
+        }
+
+        Some(evt)
+    }
+}
+
+impl Deref for ScriptedClient {
+    type Target = ImplClient<Self>;
+
+    fn deref(&self) -> &ImplClient<Self> {
+        unsafe { std::mem::transmute(self) }
+    }
+}
+
+impl DerefMut for ScriptedClient {
+    fn deref_mut(&mut self) -> &mut ImplClient<Self> {
+        unsafe { std::mem::transmute(self) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::future::block_on;
+    use tokio::r#await;
+
+    fn login(id: u16) -> server::Login {
+        server::Login {
+            success: true,
+            id: Player(id),
+            team: Team(id),
+            clock: 0,
+            token: "none".to_owned(),
+            ty: GameType::FFA,
+            room: "test".to_owned(),
+            players: vec![server::LoginPlayer {
+                id: Player(id),
+                status: PlayerStatus::Alive,
+                level: Level(0),
+                name: "test".to_owned(),
+                ty: PlaneType::Predator,
+                team: Team(id),
+                pos: Position::new(0.0, 0.0),
+                rot: Rotation::new(0.0),
+                flag: FlagCode::default(),
+                upgrades: Upgrades::default(),
+            }],
+        }
+    }
+
+    const FRAME: Duration = Duration::from_millis(16);
+
+    /// A client that is logged in as player 5 at the
+    /// origin, facing straight up.
+    fn logged_in() -> ScriptedClient {
+        let mut client = ScriptedClient::new();
+        client.world.handle_packet(&login(5).into());
+        client
+    }
+
+    fn keys_sent(client: &ScriptedClient) -> Vec<(KeyCode, bool)> {
+        client
+            .sent()
+            .iter()
+            .filter_map(|p| match p {
+                ClientPacket::Key(p) => Some((p.key, p.state)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn player_new(id: u16, pos: Position) -> server::PlayerNew {
+        server::PlayerNew {
+            id: Player(id),
+            status: PlayerStatus::Alive,
+            name: format!("player{}", id),
+            ty: PlaneType::Predator,
+            team: Team(id),
+            pos,
+            rot: Rotation::new(0.0),
+            flag: FlagCode::default(),
+            upgrades: Upgrades::default(),
+        }
+    }
+
+    #[test]
+    fn wait_for_login_skips_other_events() {
+        let mut client = ScriptedClient::new();
+        client.push_frames(Instant::now(), FRAME, 3);
+        client.push_packet(login(7));
+        client.push_frame(Instant::now());
+
+        let (client, login) = block_on(async move {
+            let login = r#await!(client.wait_for_login()).unwrap();
+            (client, login)
+        });
+
+        assert_eq!(login.map(|p| p.id.0), Some(7));
+        assert_eq!(client.world.me.id, 7);
+        assert_eq!(client.remaining(), 1);
+        assert!(client.sent().is_empty());
+    }
+
+    #[test]
+    fn wait_for_login_without_login() {
+        let mut client = ScriptedClient::new();
+        client.push_frames(Instant::now(), FRAME, 3);
+
+        let login = block_on(async move { r#await!(client.wait_for_login()).unwrap() });
+
+        assert!(login.is_none());
+    }
+
+    #[test]
+    fn turn_releases_key_on_target() {
+        let mut client = logged_in();
+        // Echo the turn key back as the server would
+        client.push_packet(server::PlayerUpdate {
+            clock: 0,
+            id: Player(5),
+            keystate: ServerKeyState {
+                right: true,
+                ..Default::default()
+            },
+            upgrades: Upgrades::default(),
+            pos: Position::new(0.0, 0.0),
+            rot: Rotation::new(0.0),
+            speed: Velocity::new(0.0, 0.0),
+        });
+        client.push_frames(Instant::now(), FRAME, 200);
+
+        let (client, error) = block_on(async move {
+            let error = r#await!(client.turn(Rotation::new(1.0))).unwrap();
+            (client, error)
+        });
+
+        assert_eq!(
+            keys_sent(&client),
+            vec![(KeyCode::Right, true), (KeyCode::Right, false)]
+        );
+        // The server keeps turning for the frame it takes
+        // for the release to arrive.
+        assert!(error.inner().abs() < 0.1, "error = {:?}", error);
+        assert!(client.remaining() > 100);
+    }
+
+    #[test]
+    fn turn_gives_up_at_deadline() {
+        let mut client = logged_in();
+        client.push_frames(Instant::now(), FRAME, 200);
+
+        let (client, error) = block_on(async move {
+            let error = r#await!(client.turn(Rotation::new(1.0))).unwrap();
+            (client, error)
+        });
+
+        // The server never confirms the key so the plane
+        // doesn't turn at all.
+        assert_eq!(
+            keys_sent(&client),
+            vec![(KeyCode::Right, true), (KeyCode::Right, false)]
+        );
+        assert!((error.inner() - 1.0).abs() < 1e-3, "error = {:?}", error);
+        assert!(client.remaining() > 0);
+    }
+
+    #[test]
+    fn follow_until_player_leaves() {
+        let start = Instant::now();
+        let mut client = logged_in();
+        // Straight ahead so that no turning is needed
+        client.push_packet(player_new(2, Position::new(0.0, -1000.0)));
+        client.push_frames(start, FRAME, 20);
+        client.push_packet(server::PlayerLeave { id: Player(2) });
+        client.push_frames(start + FRAME * 20, FRAME, 20);
+
+        let client = block_on(async move {
+            r#await!(client.follow(2)).unwrap();
+            client
+        });
+
+        let keys = keys_sent(&client);
+        assert_eq!(keys.first(), Some(&(KeyCode::Up, true)));
+        assert_eq!(keys.last(), Some(&(KeyCode::Up, false)));
+        assert!(keys
+            .iter()
+            .all(|&(key, _)| key != KeyCode::Left && key != KeyCode::Right));
+        assert!(!client.world.keys.up);
+        assert!(client.remaining() > 0);
+    }
+
+    #[test]
+    fn run_straight_at_stops_when_close() {
+        let mut client = logged_in();
+        client.push_frames(Instant::now(), FRAME, 200);
+
+        let client = block_on(async move {
+            r#await!(client.run_straight_at(Position::new(0.0, -50.0))).unwrap();
+            client
+        });
+
+        assert_eq!(
+            keys_sent(&client),
+            vec![
+                (KeyCode::Up, true),
+                (KeyCode::Down, true),
+                (KeyCode::Down, false),
+                (KeyCode::Up, false),
+            ]
+        );
+        assert!(client.remaining() > 0);
+    }
+
+    #[test]
+    fn respawn_sends_command() {
+        let mut client = ScriptedClient::new();
+        client.push_packet(login(5));
+        client.push_packet(server::PlayerRespawn {
+            id: Player(5),
+            pos: Position::new(100.0, 100.0),
+            rot: Rotation::new(0.0),
+            upgrades: Upgrades::default(),
+        });
+
+        let client = block_on(async move {
+            r#await!(client.next()).unwrap();
+            r#await!(client.respawn(PlaneType::Mohawk)).unwrap();
+            client
+        });
+
+        assert_eq!(client.world.me.id, 5);
+        assert_eq!(client.remaining(), 0);
+
+        match client.sent() {
+            [ClientPacket::Command(p)] => {
+                assert_eq!(p.com, "respawn");
+                assert_eq!(p.data, (PlaneType::Mohawk as u8).to_string());
+            }
+            sent => panic!("Unexpected packets {:?}", sent),
+        }
+    }
+}
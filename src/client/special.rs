@@ -0,0 +1,165 @@
+use std::time::{Duration, Instant};
+
+use airmash_protocol::*;
+
+use super::*;
+use crate::consts::CONFIG;
+
+/// Extra time, on top of a round trip, to wait for the
+/// server to show that our special was used.
+const SPECIAL_GRACE: Duration = Duration::from_millis(250);
+
+impl<T: Client> ImplClient<T> {
+    /// Whether our plane has the energy needed for its
+    /// special and the delay since it was last used
+    /// has passed.
+    pub fn can_use_special(&self) -> bool {
+        let me = self.world().get_me();
+        let info = &CONFIG.planes[me.plane];
+
+        let cooled_down = match self.world().me.special.last_activated {
+            Some(last) => last.elapsed() >= info.special_delay,
+            None => true,
+        };
+
+        cooled_down && me.energy >= info.special_energy
+    }
+
+    /// Use the special ability of whatever plane we are
+    /// currently flying.
+    ///
+    /// - Predator: start boosting. The special key is left
+    ///   pressed, release it to stop.
+    /// - Goliath: repel.
+    /// - Mohawk: enter strafe mode. The special key is left
+    ///   pressed, release it to stop.
+    /// - Tornado: fire a triple shot.
+    /// - Prowler: toggle stealth.
+    ///
+    /// Returns whether the server showed the ability
+    /// actually activating. Nothing is sent if we don't
+    /// have the energy to use it.
+    pub async fn use_special(&mut self) -> ClientResult<bool> {
+        use self::PlaneType::*;
+
+        if !self.can_use_special() {
+            return Ok(false);
+        }
+
+        let hold = match self.world().get_me().plane {
+            Predator | Mohawk => true,
+            Goliath | Tornado | Prowler => false,
+        };
+
+        self.world_mut().me.special.pending = Some(Instant::now());
+        r#await!(self.press_key(KeyCode::Special))?;
+
+        if !hold {
+            // Hold the key for a frame so that the server
+            // actually sees it.
+            r#await!(self.next_frame())?;
+            r#await!(self.release_key(KeyCode::Special))?;
+        }
+
+        r#await!(self.wait_for_special())
+    }
+
+    /// Boost for the given duration. Only works when
+    /// flying a predator.
+    ///
+    /// Returns whether the boost activated.
+    pub async fn boost_for(&mut self, dur: Duration) -> ClientResult<bool> {
+        self.require_plane(PlaneType::Predator)?;
+
+        let start = Instant::now();
+        let activated = r#await!(self.use_special())?;
+
+        if activated {
+            r#await!(self.wait_until(start + dur))?;
+        }
+
+        r#await!(self.release_key(KeyCode::Special))?;
+
+        Ok(activated)
+    }
+
+    /// Repel nearby players and missiles. Only works when
+    /// flying a goliath.
+    pub async fn repel(&mut self) -> ClientResult<bool> {
+        self.require_plane(PlaneType::Goliath)?;
+        r#await!(self.use_special())
+    }
+
+    /// Toggle stealth. Only works when flying a prowler.
+    ///
+    /// Returns whether the server confirmed the change,
+    /// use [`SpecialState::stealthed`] to see which way
+    /// it went.
+    ///
+    /// [`SpecialState::stealthed`]: crate::SpecialState::stealthed
+    pub async fn toggle_stealth(&mut self) -> ClientResult<bool> {
+        self.require_plane(PlaneType::Prowler)?;
+        r#await!(self.use_special())
+    }
+
+    /// Strafe left for the given duration. Only works
+    /// when flying a mohawk.
+    pub async fn strafe_left(&mut self, dur: Duration) -> ClientResult<bool> {
+        r#await!(self.strafe(KeyCode::Left, dur))
+    }
+
+    /// Strafe right for the given duration. Only works
+    /// when flying a mohawk.
+    pub async fn strafe_right(&mut self, dur: Duration) -> ClientResult<bool> {
+        r#await!(self.strafe(KeyCode::Right, dur))
+    }
+
+    async fn strafe(&mut self, key: KeyCode, dur: Duration) -> ClientResult<bool> {
+        self.require_plane(PlaneType::Mohawk)?;
+
+        let start = Instant::now();
+        r#await!(self.press_key(key))?;
+        let activated = r#await!(self.use_special())?;
+
+        if activated {
+            r#await!(self.wait_until(start + dur))?;
+        }
+
+        r#await!(self.release_key(KeyCode::Special))?;
+        r#await!(self.release_key(key))?;
+
+        Ok(activated)
+    }
+
+    fn require_plane(&self, plane: PlaneType) -> ClientResult<()> {
+        let current = self.world().get_me().plane;
+
+        if current != plane {
+            return Err(ClientError::WrongPlane {
+                expected: plane,
+                actual: current,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Process events until the server confirms our
+    /// special or it becomes clear that it won't.
+    async fn wait_for_special(&mut self) -> ClientResult<bool> {
+        let timeout = Duration::from_millis(self.world().ping as u64) + SPECIAL_GRACE;
+        let deadline = Instant::now() + timeout;
+
+        while self.world().me.special.pending.is_some() {
+            match r#await!(self.next_frame())? {
+                Some(now) if now < deadline => (),
+                _ => {
+                    self.world_mut().me.special.pending = None;
+                    return Ok(false);
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
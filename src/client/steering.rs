@@ -0,0 +1,175 @@
+use std::f32::consts::PI;
+use std::time::{Duration, Instant};
+
+use airmash_protocol::*;
+
+use super::*;
+use crate::consts::{self, BASE_DIR};
+use crate::game::World;
+
+/// Headings closer than this to the target are
+/// considered to be on target.
+const TURN_TOLERANCE: f32 = 0.05;
+
+/// Extra time given to a turn beyond how long it
+/// should take before giving up on it.
+const TURN_GRACE: Duration = Duration::from_millis(500);
+
+/// The signed shortest rotation from `cur` to `tgt`.
+///
+/// The basic idea comes from this SO answer
+/// https://stackoverflow.com/questions/9505862/shortest-distance-between-two-degree-marks-on-a-circle
+pub(crate) fn heading_error(tgt: Rotation, cur: Rotation) -> Rotation {
+    let pi2 = 2.0 * PI;
+    let mut dist = ((tgt - cur).inner() % pi2 + pi2) % pi2;
+
+    if dist > PI {
+        dist -= pi2;
+    }
+
+    Rotation::new(dist)
+}
+
+/// The heading that points from `from` towards `to`.
+pub(crate) fn angle_to(from: Position, to: Position) -> Rotation {
+    let rel = (to - from).normalized();
+    let mut angle = Vector2::dot(rel, BASE_DIR).acos();
+
+    if rel.x < 0.0.into() {
+        angle = 2.0 * PI - angle;
+    }
+
+    angle.into()
+}
+
+/// Predict what our heading will be by the time
+/// that a key sent now reaches the server.
+///
+/// The position we have for our own plane is
+/// already half a round trip old and the key will
+/// take another half to arrive, so this runs the
+/// physics forward by the full ping using the keys
+/// that we have sent so far.
+fn predicted_rot(world: &World) -> Rotation {
+    let mut me = world.get_me().clone();
+    me.keystate.left = world.keys.left;
+    me.keystate.right = world.keys.right;
+    me.keystate.strafe = false;
+
+    let latency = Duration::from_millis(world.ping as u64);
+    World::update_player(&mut me, latency.into());
+
+    me.rot
+}
+
+/// The turn key that should be held right now to end
+/// up facing `tgt`, or `None` if we will already be
+/// close enough once any key we send now arrives.
+pub(crate) fn turn_key(world: &World, tgt: Rotation) -> Option<KeyCode> {
+    let rotrate = consts::rotation_rate(world.get_me().plane);
+    // Half a frame of turning either way is as good as we can do
+    let tolerance = TURN_TOLERANCE.max(rotrate.inner() * 0.5);
+    let error = heading_error(tgt, predicted_rot(world)).inner();
+
+    if error.abs() <= tolerance {
+        None
+    } else if error < 0.0 {
+        Some(KeyCode::Left)
+    } else {
+        Some(KeyCode::Right)
+    }
+}
+
+impl<T: Client> ImplClient<T> {
+    /// The round trip time to the server.
+    fn latency(&self) -> Duration {
+        Duration::from_millis(self.world().ping as u64)
+    }
+
+    /// Hold down at most one of the turn keys.
+    async fn set_turn_key(&mut self, key: Option<KeyCode>) -> ClientResult<()> {
+        for &k in [KeyCode::Left, KeyCode::Right].iter() {
+            let state = key == Some(k);
+
+            if self.world().keys.get(k) != state {
+                r#await!(self.send_key(k, state))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Turn the plane by a given rotation.
+    ///
+    /// See [`turn_to`](ImplClient::turn_to) for details.
+    pub async fn turn(&mut self, rot: Rotation) -> ClientResult<Rotation> {
+        let tgt = self.world().get_me().rot + rot;
+
+        r#await!(self.turn_to(tgt))
+    }
+
+    /// Turn to a given angle.
+    ///
+    /// Every frame this predicts where our heading will
+    /// be once a key press reaches the server and releases
+    /// the turn key as soon as that hits the target. This
+    /// keeps working when ping changes during the turn.
+    ///
+    /// Returns the remaining error between our heading
+    /// and the target once the turn has settled. It may
+    /// still be nonzero since the plane can only turn in
+    /// whole frames.
+    pub async fn turn_to(&mut self, tgt: Rotation) -> ClientResult<Rotation> {
+        let rotrate = consts::rotation_rate(self.world().get_me().plane);
+        // Turning by PI is the most we should ever need
+        let max_turn: Duration = Time::new(PI / rotrate.inner()).into();
+        let deadline = Instant::now() + max_turn + self.latency() * 2 + TURN_GRACE;
+
+        loop {
+            let key = turn_key(self.world(), tgt);
+
+            r#await!(self.set_turn_key(key))?;
+
+            if key.is_none() {
+                break;
+            }
+
+            match r#await!(self.next_frame())? {
+                Some(now) if now < deadline => (),
+                _ => {
+                    r#await!(self.set_turn_key(None))?;
+                    break;
+                }
+            }
+        }
+
+        // Give the release time to make it to the server
+        // so that the error we report is what actually
+        // happened.
+        let settle = self.latency();
+        r#await!(self.wait(settle))?;
+
+        Ok(heading_error(tgt, self.world().get_me().rot))
+    }
+
+    /// Point the plane at a given point.
+    ///
+    /// See [`turn_to`](ImplClient::turn_to) for details.
+    pub async fn point_at(&mut self, pos: Position) -> ClientResult<Rotation> {
+        let angle = angle_to(self.world().get_me().pos, pos);
+
+        r#await!(self.turn_to(angle))
+    }
+
+    /// Process events until the next frame. Returns
+    /// `None` if the connection closes first.
+    pub(crate) async fn next_frame(&mut self) -> ClientResult<Option<Instant>> {
+        while let Some(evt) = r#await!(self.next())? {
+            if let ClientEvent::Frame(now) = evt {
+                return Ok(Some(now));
+            }
+        }
+
+        Ok(None)
+    }
+}
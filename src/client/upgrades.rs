@@ -0,0 +1,167 @@
+use airmash_protocol::*;
+
+use std::time::{Duration, Instant};
+
+use super::commands::COMMAND_TIMEOUT;
+use super::*;
+use crate::game::{ClientUpgrades, GameEvent, UpgradeStrategy};
+
+/// How long to wait for the server to confirm an upgrade
+/// bought by a strategy before trying again.
+pub(crate) const UPGRADE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether `evt` settles the purchase of `pending`, either
+/// by confirming it, rejecting it or timing out.
+pub(crate) fn upgrade_settled(pending: (UpgradeType, Instant), evt: &ClientEvent) -> bool {
+    let (ty, deadline) = pending;
+
+    match evt {
+        ClientEvent::Packet(ServerPacket::PlayerUpgrade(p)) => p.ty == ty,
+        ClientEvent::Packet(ServerPacket::Error(p)) => match p.error {
+            ErrorType::NotEnoughUpgrades | ErrorType::UnknownCommand => true,
+            _ => false,
+        },
+        ClientEvent::Frame(now) => *now > deadline,
+        _ => false,
+    }
+}
+
+impl<T: Client> ImplClient<T> {
+    /// Buy the next level of an upgrade.
+    ///
+    /// This checks that we have enough unused points
+    /// before sending anything, then waits for the server
+    /// to confirm the purchase with a `PlayerUpgrade`
+    /// packet. Returns the new upgrade levels.
+    ///
+    /// Only errors that could be a response to the upgrade
+    /// command make this fail, others are ignored.
+    pub async fn buy_upgrade(&mut self, ty: UpgradeType) -> ClientResult<ClientUpgrades> {
+        let cost = match self.world().upgrade_cost(ty) {
+            Some(cost) => cost,
+            None => return Err(ClientError::InvalidUpgrade(ty)),
+        };
+        let available = self.world().me.upgrades.unused;
+        if cost > available {
+            return Err(ClientError::NotEnoughUpgrades { available, cost });
+        }
+
+        r#await!(self.send_upgrade(ty))?;
+        let is_error = |e| match e {
+            ErrorType::NotEnoughUpgrades | ErrorType::UnknownCommand => true,
+            _ => false,
+        };
+        r#await!(self.wait_for_reply_or_error(COMMAND_TIMEOUT, is_error, |p| match p {
+            ServerPacket::PlayerUpgrade(p) if p.ty == ty => Some(()),
+            _ => None,
+        }))?;
+
+        Ok(self.world().me.upgrades)
+    }
+
+    /// Set the strategy that will be used to spend upgrade
+    /// points whenever we gain some. Passing `None` will
+    /// leave points unspent.
+    pub fn set_upgrade_strategy(&mut self, strategy: Option<UpgradeStrategy>) {
+        self.world_mut().upgrade_strategy = strategy;
+        self.world_mut().upgrade_pending = None;
+    }
+
+    /// Pick the upgrade that the current strategy wants to
+    /// buy in response to `evt`, if any.
+    ///
+    /// This is run from `next` on every event. It starts
+    /// when we gain points and buys one upgrade at a time,
+    /// waiting for the server to confirm each one before
+    /// asking the strategy again. It never waits for any
+    /// events itself.
+    pub(crate) fn strategy_upgrade(&mut self, evt: &ClientEvent) -> Option<UpgradeType> {
+        let strategy = self.world().upgrade_strategy?;
+
+        match self.world().upgrade_pending {
+            Some(pending) if upgrade_settled(pending, evt) => {
+                self.world_mut().upgrade_pending = None;
+            }
+            Some(_) => return None,
+            None => match evt {
+                ClientEvent::Game(GameEvent::UpgradePointsGained(_)) => (),
+                _ => return None,
+            },
+        }
+
+        let ty = match strategy.next_upgrade(self.world()) {
+            Some(ty) if self.world().can_afford_upgrade(ty) => ty,
+            _ => return None,
+        };
+
+        self.world_mut().upgrade_pending = Some((ty, Instant::now() + UPGRADE_TIMEOUT));
+        Some(ty)
+    }
+
+    pub(crate) async fn send_upgrade(&mut self, ty: UpgradeType) -> ClientResult<()> {
+        r#await!(self.send_command("upgrade", (ty as u8).to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::future::block_on;
+    use crate::game::strategies;
+    use crate::ScriptedClient;
+
+    fn upgrade_commands(client: &ScriptedClient) -> Vec<String> {
+        client
+            .sent()
+            .iter()
+            .filter_map(|p| match p {
+                ClientPacket::Command(p) if p.com == "upgrade" => Some(p.data.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn strategy_spends_points_when_gained() {
+        let mut client = ScriptedClient::new();
+        client.world.me.id = 1;
+        client.world.players.insert(
+            1,
+            crate::game::Player {
+                id: 1,
+                ..Default::default()
+            },
+        );
+        client.set_upgrade_strategy(Some(UpgradeStrategy(strategies::speed_first)));
+
+        client.push_frame(Instant::now());
+        client.push_packet(server::ScoreUpdate {
+            id: Player(1),
+            score: Score(0),
+            earnings: Score(0),
+            upgrades: 2,
+            total_kills: 0,
+            total_deaths: 0,
+        });
+        client.push_frame(Instant::now());
+        client.push_packet(server::PlayerUpgrade {
+            upgrades: 1,
+            ty: UpgradeType::Speed,
+            speed: 1,
+            defense: 0,
+            energy: 0,
+            missile: 0,
+        });
+
+        let client = block_on(async move {
+            while r#await!(client.next()).unwrap().is_some() {}
+            client
+        });
+
+        // One purchase when the points arrived and another
+        // once the server confirmed the first.
+        let speed = (UpgradeType::Speed as u8).to_string();
+        assert_eq!(upgrade_commands(&client), vec![speed.clone(), speed]);
+        assert!(client.world.upgrade_pending.is_some());
+    }
+}
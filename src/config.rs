@@ -34,6 +34,14 @@ pub struct PlaneInfo {
     pub fire_energy: Energy,
     pub fire_delay: Duration,
 
+    // Energy needed to use the special. For the predator
+    // this is drained every frame while boosting instead.
+    pub special_energy: Energy,
+    pub special_delay: Duration,
+
+    // Radius of the circle used for terrain collisions
+    pub hit_radius: Distance,
+
     // Type of missile that the plane fires
     pub missile_type: MobType,
     // Offset of missile (in the Y dir) when fired
@@ -80,7 +88,6 @@ pub struct Config {
     pub planes: PlaneInfos,
     pub mobs: MobInfos,
     pub upgrades: UpgradeInfos,
-    pub admin_enabled: bool,
     pub spawn_shield_duration: Duration,
     pub shield_duration: Duration,
     pub inferno_duration: Duration,
@@ -129,6 +136,11 @@ impl Default for PlaneInfos {
 
                 fire_energy: Energy::new(0.6),
 
+                special_energy: Energy::new(0.01),
+                special_delay: Duration::from_millis(0),
+
+                hit_radius: Distance::new(23.0),
+
                 missile_type: MobType::PredatorMissile,
                 missile_offset: Distance::new(35.0),
             },
@@ -156,6 +168,11 @@ impl Default for PlaneInfos {
 
                 fire_energy: Energy::new(0.9),
 
+                special_energy: Energy::new(0.5),
+                special_delay: Duration::from_millis(1000),
+
+                hit_radius: Distance::new(35.0),
+
                 missile_type: MobType::GoliathMissile,
                 missile_offset: Distance::new(35.0),
             },
@@ -183,6 +200,11 @@ impl Default for PlaneInfos {
 
                 fire_energy: Energy::new(0.3),
 
+                special_energy: Energy::new(0.0),
+                special_delay: Duration::from_millis(0),
+
+                hit_radius: Distance::new(20.0),
+
                 missile_type: MobType::MohawkMissile,
                 // This will have to be a special case
                 missile_offset: Distance::new(10.0),
@@ -211,6 +233,11 @@ impl Default for PlaneInfos {
 
                 fire_energy: Energy::new(0.5),
 
+                special_energy: Energy::new(0.9),
+                special_delay: Duration::from_millis(500),
+
+                hit_radius: Distance::new(26.0),
+
                 missile_type: MobType::TornadoSingleMissile,
                 missile_offset: Distance::new(40.0),
             },
@@ -238,6 +265,11 @@ impl Default for PlaneInfos {
 
                 fire_energy: Energy::new(0.75),
 
+                special_energy: Energy::new(0.6),
+                special_delay: Duration::from_millis(1500),
+
+                hit_radius: Distance::new(24.0),
+
                 missile_type: MobType::ProwlerMissile,
                 missile_offset: Distance::new(35.0),
             },
@@ -408,7 +440,6 @@ impl Default for Config {
             planes: Default::default(),
             mobs: Default::default(),
             upgrades: Default::default(),
-            admin_enabled: true,
             spawn_shield_duration: Duration::from_secs(2),
             shield_duration: Duration::from_secs(10),
             inferno_duration: Duration::from_secs(10),
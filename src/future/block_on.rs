@@ -0,0 +1,36 @@
+use std::future::Future;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use futures::future::lazy;
+use tokio::r#await;
+use tokio::runtime::Runtime;
+
+/// How long `block_on` waits for the future before
+/// failing the test.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Run a future on a new runtime and return its output.
+///
+/// Unlike `tokio::run_async` this doesn't wait for any
+/// tasks that the future spawned (e.g. a test server),
+/// and it fails instead of hanging if the future panics.
+pub(crate) fn block_on<F>(future: F) -> F::Output
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let runtime = Runtime::new().expect("Failed to start a runtime");
+    let (tx, rx) = mpsc::channel();
+
+    runtime.executor().spawn(lazy(move || {
+        tokio::spawn_async(async move {
+            let _ = tx.send(r#await!(future));
+        });
+
+        Ok::<(), ()>(())
+    }));
+
+    rx.recv_timeout(TIMEOUT)
+        .expect("The future panicked or timed out")
+}
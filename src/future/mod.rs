@@ -1,3 +1,7 @@
 mod boxed_future;
+#[cfg(test)]
+mod block_on;
 
 pub(crate) use self::boxed_future::BoxedFuture;
+#[cfg(test)]
+pub(crate) use self::block_on::block_on;
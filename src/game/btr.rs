@@ -0,0 +1,48 @@
+use crate::protocol::*;
+
+use hashbrown::HashMap;
+
+/// The edge of the safe zone in a battle royale game.
+#[derive(Debug, Copy, Clone)]
+pub struct Firewall {
+    /// The centre of the safe zone.
+    pub pos: Position,
+    /// The current radius of the safe zone. This is
+    /// extrapolated every frame from the last update.
+    pub radius: f32,
+    /// How fast the radius shrinks, in distance per
+    /// frame (1/60th of a second).
+    pub speed: f32,
+}
+
+impl Firewall {
+    /// Whether `pos` is within the safe zone.
+    pub fn contains(&self, pos: Position) -> bool {
+        let dx = pos.x.inner() - self.pos.x.inner();
+        let dy = pos.y.inner() - self.pos.y.inner();
+
+        dx * dx + dy * dy < self.radius * self.radius
+    }
+
+    pub(crate) fn update(&mut self, delta: Time) {
+        self.radius = (self.radius - self.speed * delta.inner()).max(0.0);
+    }
+}
+
+/// Battle royale state for the current game.
+///
+/// This is only updated in BTR games; in other game
+/// modes it stays at its default value.
+#[derive(Debug, Default, Clone)]
+pub struct BtrState {
+    /// `None` until the server sends the first
+    /// firewall update.
+    pub firewall: Option<Firewall>,
+    /// The number of players still alive.
+    pub players_alive: u16,
+    /// The number of games each player has won.
+    pub wins: HashMap<u16, u16>,
+    /// The name of the player that won the last
+    /// game, if the server has announced one.
+    pub winner: Option<String>,
+}
@@ -0,0 +1,26 @@
+use std::time::Instant;
+
+/// The maximum number of messages kept in
+/// [`World::chat`](crate::World::chat).
+pub const CHAT_HISTORY_LEN: usize = 100;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ChatKind {
+    Public,
+    Team,
+    Whisper,
+    Say,
+}
+
+#[derive(Clone, Debug)]
+pub struct ChatMessage {
+    pub kind: ChatKind,
+    /// The player that sent the message.
+    pub from: u16,
+    /// The recipient of the message, only set
+    /// for whispers.
+    pub to: Option<u16>,
+    pub text: String,
+    /// When this client received the message.
+    pub time: Instant,
+}
@@ -0,0 +1,82 @@
+use crate::protocol::*;
+
+/// The team that owns the blue flag in CTF.
+pub const BLUE_TEAM: Team = Team(1);
+/// The team that owns the red flag in CTF.
+pub const RED_TEAM: Team = Team(2);
+
+/// How far a flag can be from its base and still be
+/// considered to be sitting on it.
+const FLAG_BASE_TOLERANCE: f32 = 10.0;
+
+/// Where the flag belonging to `team` sits when nobody
+/// has taken it.
+pub fn flag_base(team: Team) -> Option<Position> {
+    match team {
+        BLUE_TEAM => Some(Position::new(-9670.0, -1470.0)),
+        RED_TEAM => Some(Position::new(8600.0, -940.0)),
+        _ => None,
+    }
+}
+
+/// Whether `pos` is at the base of the flag belonging
+/// to `team`.
+pub(crate) fn is_flag_base(team: Team, pos: Position) -> bool {
+    match flag_base(team) {
+        Some(base) => (pos - base).length().inner() < FLAG_BASE_TOLERANCE,
+        None => false,
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct FlagState {
+    pub pos: Position,
+    /// The player currently carrying the flag.
+    pub carrier: Option<u16>,
+    /// Whether the server has told us about this flag
+    /// yet. Until then `pos` is meaningless.
+    pub seen: bool,
+}
+
+/// Capture-the-flag state for the current game.
+///
+/// This is only updated in CTF games; in other game
+/// modes it stays at its default value.
+#[derive(Debug, Default, Clone)]
+pub struct CtfState {
+    pub blue: FlagState,
+    pub red: FlagState,
+    pub blue_score: u8,
+    pub red_score: u8,
+    /// The team that won the last game, if the
+    /// server has announced one.
+    pub winner: Option<Team>,
+}
+
+impl CtfState {
+    /// The flag belonging to `team`.
+    pub fn flag(&self, team: Team) -> Option<&FlagState> {
+        match team {
+            BLUE_TEAM => Some(&self.blue),
+            RED_TEAM => Some(&self.red),
+            _ => None,
+        }
+    }
+
+    pub fn flag_mut(&mut self, team: Team) -> Option<&mut FlagState> {
+        match team {
+            BLUE_TEAM => Some(&mut self.blue),
+            RED_TEAM => Some(&mut self.red),
+            _ => None,
+        }
+    }
+
+    /// The number of captures that `team` has made.
+    pub fn score(&self, team: Team) -> Option<u8> {
+        match team {
+            BLUE_TEAM => Some(self.blue_score),
+            RED_TEAM => Some(self.red_score),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use super::ChatMessage;
+use crate::protocol::*;
+
+use std::time::Duration;
+
+/// High-level events derived from the packets that
+/// the server sends.
+///
+/// These are produced by [`World::handle_packet`]
+/// after the world has been updated, so the world
+/// already reflects the event when it is observed.
+///
+/// [`World::handle_packet`]: crate::World::handle_packet
+#[derive(Clone, Debug)]
+pub enum GameEvent {
+    /// A new player entered the game.
+    PlayerJoined(u16),
+    /// A player left the game. Since the player will
+    /// have been removed from the world by the time
+    /// this is seen their name is included here.
+    PlayerLeft { id: u16, name: String },
+    /// A player was killed. `killer` is `None` when
+    /// the server didn't say who killed them.
+    PlayerKilled {
+        victim: u16,
+        killer: Option<u16>,
+        pos: Position,
+    },
+    /// A player fired a missile.
+    MissileFired { owner: u16, mob: u16, ty: MobType },
+    /// A player respawned.
+    PlayerRespawned(u16),
+    /// A player picked up a powerup. The server only
+    /// tells us about our own powerups.
+    PowerupGained {
+        player: u16,
+        ty: PowerupType,
+        duration: Duration,
+    },
+    /// We gained upgrade points. Contains the new
+    /// number of unused points.
+    UpgradePointsGained(u16),
+    /// A player was moved to a different team.
+    TeamChanged { player: u16, old: Team, new: Team },
+
+    /// A chat message was received.
+    Chat(ChatMessage),
+    /// A vote to mute a player passed.
+    PlayerMuted(u16),
+
+    /// A player picked up the flag belonging to `team`.
+    FlagTaken { team: Team, carrier: u16 },
+    /// The flag belonging to `team` was dropped.
+    FlagDropped { team: Team, pos: Position },
+    /// The flag belonging to `team` was returned to
+    /// its base without being captured.
+    FlagReturned { team: Team },
+    /// The flag belonging to `team` was captured by
+    /// the opposing team.
+    FlagCaptured { team: Team, carrier: Option<u16> },
+    /// A CTF game finished.
+    CtfGameOver { winner: Team },
+    /// A BTR game finished. The server only gives the
+    /// name of the winning player.
+    BtrGameOver { winner: String },
+}
@@ -1,3 +1,5 @@
+use crate::protocol::KeyCode;
+
 use std::time::Instant;
 
 #[derive(Debug, Default, Copy, Clone)]
@@ -9,11 +11,94 @@ pub struct ClientUpgrades {
     pub unused: u16,
 }
 
+/// What we know about our own plane's special
+/// ability.
+///
+/// The server doesn't reply to special key presses
+/// directly so this tracks whether we are waiting on
+/// one and when the server last showed it happening.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SpecialState {
+    /// When we pressed the special key, if the server
+    /// hasn't acted on it yet.
+    pub pending: Option<Instant>,
+    /// When the server last confirmed our special.
+    pub last_activated: Option<Instant>,
+    pub boosting: bool,
+    pub stealthed: bool,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct CurrentPlayer {
     pub id: u16,
     pub upgrades: ClientUpgrades,
     pub powerup_expiry: Option<Instant>,
+    /// When we last fired a missile.
+    pub last_fire: Option<Instant>,
+    pub special: SpecialState,
+    /// The keys that `evade` has changed from the state
+    /// that the bot left them in.
+    pub evading: ClientKeyState,
+    /// The state that each key in `evading` was in before
+    /// `evade` changed it.
+    pub pre_evasion: ClientKeyState,
 
     pub token: String,
 }
+
+/// The keys that this client has told the
+/// server are currently pressed.
+///
+/// Unlike [`ServerKeyState`] this is updated as
+/// soon as the key packet is sent instead of when
+/// the server echoes it back.
+///
+/// [`ServerKeyState`]: crate::protocol::ServerKeyState
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ClientKeyState {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub fire: bool,
+    pub special: bool,
+}
+
+impl ClientKeyState {
+    pub fn get(&self, key: KeyCode) -> bool {
+        use self::KeyCode::*;
+
+        match key {
+            Up => self.up,
+            Down => self.down,
+            Left => self.left,
+            Right => self.right,
+            Fire => self.fire,
+            Special => self.special,
+        }
+    }
+
+    pub fn set(&mut self, key: KeyCode, state: bool) {
+        use self::KeyCode::*;
+
+        match key {
+            Up => self.up = state,
+            Down => self.down = state,
+            Left => self.left = state,
+            Right => self.right = state,
+            Fire => self.fire = state,
+            Special => self.special = state,
+        }
+    }
+
+    /// All the keys that are currently pressed.
+    pub fn pressed(&self) -> Vec<KeyCode> {
+        use self::KeyCode::*;
+
+        [Up, Down, Left, Right, Fire, Special]
+            .iter()
+            .cloned()
+            .filter(|&key| self.get(key))
+            .collect()
+    }
+}
@@ -2,12 +2,25 @@
 
 #![allow(dead_code)]
 
+mod btr;
+mod chat;
+mod ctf;
+mod event;
 mod me;
 mod mob;
 mod player;
+mod threat;
+mod upgrades;
 mod world;
 
-pub use self::me::{ClientUpgrades, CurrentPlayer};
+pub use self::btr::{BtrState, Firewall};
+pub use self::chat::{ChatKind, ChatMessage, CHAT_HISTORY_LEN};
+pub use self::ctf::{flag_base, CtfState, FlagState, BLUE_TEAM, RED_TEAM};
+pub use self::event::GameEvent;
+pub use self::me::{ClientKeyState, ClientUpgrades, CurrentPlayer, SpecialState};
 pub use self::mob::Mob;
-pub use self::player::Player;
+pub use self::player::{Player, Powerup};
+pub use self::threat::Threat;
+pub(crate) use self::threat::closest_approach;
+pub use self::upgrades::{strategies, UpgradeStrategy, MAX_UPGRADE_LEVEL};
 pub use self::world::World;
@@ -1,6 +1,20 @@
+use crate::consts::CONFIG;
 use crate::protocol::server::*;
 use crate::protocol::*;
 
+use std::time::{Duration, Instant};
+
+/// A powerup that is currently active on a player.
+#[derive(Debug, Copy, Clone)]
+pub struct Powerup {
+    pub ty: PowerupType,
+    pub expiry: Instant,
+    /// Set once `expiry` has passed. The powerup is kept
+    /// until the server clears its flag so that the flag
+    /// isn't mistaken for a newly picked up powerup.
+    pub expired: bool,
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct Player {
     pub name: String,
@@ -33,6 +47,7 @@ pub struct Player {
     pub keystate: ServerKeyState,
     pub upgrades: Upgrades,
     pub unused_upgrades: u16,
+    pub powerup: Option<Powerup>,
 }
 
 impl Player {
@@ -43,5 +58,59 @@ impl Player {
         self.keystate = packet.keystate;
         self.upgrades = packet.upgrades;
         self.status = PlayerStatus::Alive;
+
+        self.update_powerup(CONFIG.shield_duration);
+    }
+
+    pub fn has_shield(&self) -> bool {
+        match self.powerup {
+            Some(Powerup {
+                ty: PowerupType::Shield,
+                expired: false,
+                ..
+            }) => true,
+            _ => false,
+        }
+    }
+
+    pub fn has_inferno(&self) -> bool {
+        match self.powerup {
+            Some(Powerup {
+                ty: PowerupType::Inferno,
+                expired: false,
+                ..
+            }) => true,
+            _ => false,
+        }
+    }
+
+    /// Bring `powerup` in line with the shield and inferno
+    /// flags in `upgrades`.
+    ///
+    /// The server doesn't tell us how long other players'
+    /// powerups last, so when a new one shows up we assume
+    /// it was just picked up. `shield_duration` is used for
+    /// shields since spawn shields are shorter than the
+    /// ones that are picked up.
+    pub(crate) fn update_powerup(&mut self, shield_duration: Duration) {
+        let (ty, duration) = if self.upgrades.shield {
+            (PowerupType::Shield, shield_duration)
+        } else if self.upgrades.inferno {
+            (PowerupType::Inferno, CONFIG.inferno_duration)
+        } else {
+            self.powerup = None;
+            return;
+        };
+
+        match self.powerup {
+            Some(ref powerup) if powerup.ty == ty => (),
+            _ => {
+                self.powerup = Some(Powerup {
+                    ty,
+                    expiry: Instant::now() + duration,
+                    expired: false,
+                })
+            }
+        }
     }
 }
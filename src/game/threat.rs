@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use super::world::MISSILE_RADIUS;
+use super::{Mob, Player, World};
+use crate::consts::CONFIG;
+use crate::protocol::*;
+
+/// A missile that is expected to hit a player.
+#[derive(Copy, Clone, Debug)]
+pub struct Threat {
+    /// The id of the missile.
+    pub mob: u16,
+    /// The player that fired the missile, if known.
+    pub owner: Option<u16>,
+    pub ty: MobType,
+    /// How long until the missile is expected to hit.
+    pub time_to_impact: Duration,
+    /// How much health the hit is expected to take.
+    pub damage: Health,
+}
+
+/// The closest that a missile gets to a player while
+/// both follow the client-side physics.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Approach {
+    /// The smallest gap between the missile and the
+    /// edge of the player's hitbox. Negative if the
+    /// missile hits.
+    pub gap: f32,
+    /// The number of frames until the closest approach
+    /// (or the hit).
+    pub frames: u32,
+}
+
+/// Step a player and a missile forward one frame at a
+/// time and find how close the missile comes.
+///
+/// Stops early if the missile hits the player or some
+/// terrain.
+pub(crate) fn closest_approach(mut player: Player, mut mob: Mob, frames: u32) -> Approach {
+    let radius = CONFIG.planes[player.plane].hit_radius.inner() + MISSILE_RADIUS;
+    let gap = |player: &Player, mob: &Mob| (player.pos - mob.pos).length().inner() - radius;
+
+    let mut closest = Approach {
+        gap: gap(&player, &mob),
+        frames: 0,
+    };
+
+    for frame in 1..=frames {
+        if closest.gap < 0.0 {
+            break;
+        }
+
+        World::update_player(&mut player, Time::new(1.0));
+        if !World::update_mob(&mut mob, Time::new(1.0)) {
+            break;
+        }
+
+        let current = gap(&player, &mob);
+        if current < closest.gap {
+            closest = Approach {
+                gap: current,
+                frames: frame,
+            };
+        }
+    }
+
+    closest
+}
+
+impl World {
+    /// All missiles that are expected to hit the given
+    /// player within `horizon`, soonest first.
+    ///
+    /// Both the player and the missiles are moved forward
+    /// using the same physics as [`World::update`], with
+    /// the player holding whatever keys they have now.
+    /// Missiles fired by the player's own team are ignored.
+    ///
+    /// The defense upgrade level is only known for our
+    /// own plane so the damage for other players assumes
+    /// that they have none. Players with a shield take no
+    /// damage.
+    pub fn incoming_threats(&self, player: u16, horizon: Duration) -> Vec<Threat> {
+        let target = match self.players.get(&player) {
+            Some(target) => target,
+            None => return vec![],
+        };
+        let frames = Time::from(horizon).inner().ceil() as u32;
+
+        let mut threats: Vec<_> = self
+            .mobs
+            .values()
+            .filter(|mob| mob.missile())
+            .filter(|mob| !self.is_friendly(mob, target))
+            .filter_map(|mob| {
+                let approach = closest_approach(target.clone(), *mob, frames);
+
+                if approach.gap >= 0.0 {
+                    return None;
+                }
+
+                Some(Threat {
+                    mob: mob.id,
+                    owner: mob.owner,
+                    ty: mob.ty,
+                    time_to_impact: Time::new(approach.frames as f32).into(),
+                    damage: self.missile_damage(mob.ty, target),
+                })
+            })
+            .collect();
+
+        threats.sort_by_key(|t| t.time_to_impact);
+        threats
+    }
+
+    /// Whether a missile was fired by someone on the
+    /// same team as the player.
+    pub(crate) fn is_friendly(&self, mob: &Mob, player: &Player) -> bool {
+        match mob.owner {
+            Some(owner) if owner == player.id => true,
+            Some(owner) => self
+                .players
+                .get(&owner)
+                .map(|owner| owner.team == player.team)
+                .unwrap_or(false),
+            None => false,
+        }
+    }
+
+    /// The damage that a missile of the given type will
+    /// do to the player.
+    pub fn missile_damage(&self, ty: MobType, player: &Player) -> Health {
+        let info = match CONFIG.mobs[ty].missile {
+            Some(info) => info,
+            None => return Health::new(0.0),
+        };
+
+        if player.has_shield() {
+            return Health::new(0.0);
+        }
+
+        let defense = match player.id == self.me.id {
+            true => CONFIG.upgrades.defense.factor[self.me.upgrades.defense as usize],
+            false => 1.0,
+        };
+
+        Health::new(info.damage.inner() * CONFIG.planes[player.plane].damage_factor / defense)
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt;
+
+use super::World;
+use crate::consts::CONFIG;
+use crate::protocol::*;
+
+/// The highest level that any upgrade can reach.
+pub const MAX_UPGRADE_LEVEL: u8 = 5;
+
+/// Picks which upgrade to buy next, given the current
+/// state of the world. Returning `None` will save the
+/// remaining points for later.
+///
+/// Strategies are run automatically whenever the bot
+/// gains upgrade points, see [`World::upgrade_strategy`].
+/// Bots driven by an `ActionSet` can use the
+/// [`SpendUpgrades`] action instead.
+///
+/// [`SpendUpgrades`]: crate::action::SpendUpgrades
+#[derive(Copy, Clone)]
+pub struct UpgradeStrategy(pub fn(&World) -> Option<UpgradeType>);
+
+impl UpgradeStrategy {
+    pub fn next_upgrade(&self, world: &World) -> Option<UpgradeType> {
+        (self.0)(world)
+    }
+}
+
+impl fmt::Debug for UpgradeStrategy {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "UpgradeStrategy({:p})", self.0 as *const ())
+    }
+}
+
+impl World {
+    /// The current level of the given upgrade.
+    pub fn upgrade_level(&self, ty: UpgradeType) -> Option<u8> {
+        let upgrades = &self.me.upgrades;
+
+        match ty {
+            UpgradeType::Speed => Some(upgrades.speed),
+            UpgradeType::Defense => Some(upgrades.defense),
+            UpgradeType::Energy => Some(upgrades.energy),
+            UpgradeType::Missile => Some(upgrades.missile),
+            UpgradeType::None => None,
+        }
+    }
+
+    /// The number of points needed for the next level of
+    /// the given upgrade. Returns `None` if the upgrade is
+    /// already at the maximum level.
+    pub fn upgrade_cost(&self, ty: UpgradeType) -> Option<u16> {
+        let level = self.upgrade_level(ty)?;
+        if level >= MAX_UPGRADE_LEVEL {
+            return None;
+        }
+
+        let info = match ty {
+            UpgradeType::Speed => &CONFIG.upgrades.speed,
+            UpgradeType::Defense => &CONFIG.upgrades.defense,
+            UpgradeType::Energy => &CONFIG.upgrades.energy,
+            UpgradeType::Missile => &CONFIG.upgrades.missile,
+            UpgradeType::None => return None,
+        };
+
+        Some(info.cost[level as usize + 1] as u16)
+    }
+
+    /// Whether there are enough unused points to buy the
+    /// next level of the given upgrade.
+    pub fn can_afford_upgrade(&self, ty: UpgradeType) -> bool {
+        match self.upgrade_cost(ty) {
+            Some(cost) => cost <= self.me.upgrades.unused,
+            None => false,
+        }
+    }
+}
+
+/// Some simple upgrade strategies.
+pub mod strategies {
+    use super::*;
+
+    const ALL: [UpgradeType; 4] = [
+        UpgradeType::Speed,
+        UpgradeType::Defense,
+        UpgradeType::Energy,
+        UpgradeType::Missile,
+    ];
+
+    /// Level every upgrade evenly.
+    pub fn balanced(world: &World) -> Option<UpgradeType> {
+        ALL.iter()
+            .cloned()
+            .filter(|&ty| world.can_afford_upgrade(ty))
+            .min_by_key(|&ty| world.upgrade_level(ty))
+    }
+
+    /// Max out speed first, then level everything else evenly.
+    pub fn speed_first(world: &World) -> Option<UpgradeType> {
+        if world.can_afford_upgrade(UpgradeType::Speed) {
+            return Some(UpgradeType::Speed);
+        }
+
+        balanced(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world(unused: u16, levels: [u8; 4]) -> World {
+        let mut world = World::default();
+        let upgrades = &mut world.me.upgrades;
+
+        upgrades.unused = unused;
+        upgrades.speed = levels[0];
+        upgrades.defense = levels[1];
+        upgrades.energy = levels[2];
+        upgrades.missile = levels[3];
+        world
+    }
+
+    #[test]
+    fn cost_of_next_level() {
+        let world = world(0, [0, 5, 0, 0]);
+
+        assert_eq!(world.upgrade_cost(UpgradeType::Speed), Some(1));
+        assert_eq!(world.upgrade_cost(UpgradeType::Defense), None);
+        assert_eq!(world.upgrade_cost(UpgradeType::None), None);
+    }
+
+    #[test]
+    fn affordable_upgrades() {
+        assert!(!world(0, [0; 4]).can_afford_upgrade(UpgradeType::Speed));
+        assert!(world(1, [0; 4]).can_afford_upgrade(UpgradeType::Speed));
+        assert!(!world(1, [5; 4]).can_afford_upgrade(UpgradeType::Speed));
+    }
+
+    #[test]
+    fn balanced_picks_lowest_level() {
+        assert_eq!(
+            strategies::balanced(&world(1, [2, 1, 0, 1])),
+            Some(UpgradeType::Energy)
+        );
+        assert_eq!(strategies::balanced(&world(0, [2, 1, 0, 1])), None);
+        assert_eq!(strategies::balanced(&world(3, [5; 4])), None);
+    }
+
+    #[test]
+    fn speed_first_then_balanced() {
+        assert_eq!(
+            strategies::speed_first(&world(1, [3, 0, 0, 0])),
+            Some(UpgradeType::Speed)
+        );
+        assert_eq!(
+            strategies::speed_first(&world(1, [5, 1, 0, 1])),
+            Some(UpgradeType::Energy)
+        );
+    }
+}
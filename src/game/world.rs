@@ -1,9 +1,12 @@
 use super::*;
+use super::ctf::is_flag_base;
 use super::{Mob, Player};
 use crate::consts::CONFIG;
+use crate::map::DEFAULT_TERRAIN;
 use crate::protocol::server::*;
 use crate::protocol::*;
 
+use std::collections::VecDeque;
 use std::ops::{Add, Rem};
 use std::time::{Duration, Instant};
 
@@ -20,10 +23,24 @@ pub struct World {
     pub room: String,
     pub clock: u32,
     pub key_seq: u32,
+    pub keys: ClientKeyState,
     pub ping: u16,
     pub players_game: u32,
     pub players_total: u32,
     pub last_frame: Option<Instant>,
+
+    pub ctf: CtfState,
+    pub btr: BtrState,
+    /// The most recent chat messages, oldest first.
+    pub chat: VecDeque<ChatMessage>,
+    /// Run whenever we gain upgrade points to decide what
+    /// to spend them on. `None` leaves them unspent.
+    pub upgrade_strategy: Option<UpgradeStrategy>,
+    /// The upgrade that the strategy is waiting on and
+    /// when to give up on it.
+    pub(crate) upgrade_pending: Option<(UpgradeType, Instant)>,
+
+    events: Vec<GameEvent>,
 }
 
 macro_rules! warn_unknown {
@@ -57,7 +74,43 @@ impl World {
         self.players.get_mut(&self.me.id).unwrap()
     }
 
-    pub fn handle_packet(&mut self, packet: &ServerPacket) {
+    /// The player carrying the flag that belongs to `team`.
+    pub fn flag_carrier(&self, team: Team) -> Option<u16> {
+        self.ctf.flag(team).and_then(|flag| flag.carrier)
+    }
+    /// Whether `pos` is inside the BTR safe zone.
+    ///
+    /// Outside of BTR games, or before the server has sent
+    /// the position of the firewall, everywhere is safe.
+    pub fn is_inside_safe_zone(&self, pos: Position) -> bool {
+        if self.game_ty != GameType::BTR {
+            return true;
+        }
+
+        match self.btr.firewall {
+            Some(ref firewall) => firewall.contains(pos),
+            None => true,
+        }
+    }
+
+    /// The last known position of the flag that belongs
+    /// to `team`.
+    pub fn flag_pos(&self, team: Team) -> Option<Position> {
+        let flag = self.ctf.flag(team)?;
+
+        // The server doesn't send updates for the flag while
+        // it's being carried, it moves with the carrier.
+        match flag.carrier.and_then(|id| self.players.get(&id)) {
+            Some(carrier) => Some(carrier.pos),
+            None => Some(flag.pos),
+        }
+    }
+
+    /// Update the world with a packet from the server.
+    ///
+    /// Returns the high-level events that the packet
+    /// caused, if any.
+    pub fn handle_packet(&mut self, packet: &ServerPacket) -> Vec<GameEvent> {
         use self::ServerPacket::*;
 
         match packet {
@@ -90,8 +143,23 @@ impl World {
             EventLeaveHorizon(p) => self.handle_event_leave_horizon(p),
             EventRepel(p) => self.handle_event_repel(p),
             EventStealth(p) => self.handle_event_stealth(p),
+
+            ChatPublic(p) => self.handle_chat_public(p),
+            ChatTeam(p) => self.handle_chat_team(p),
+            ChatWhisper(p) => self.handle_chat_whisper(p),
+            ChatSay(p) => self.handle_chat_say(p),
+            ChatVoteMutePassed(p) => self.handle_chat_vote_mute_passed(p),
+
+            GameFlag(p) => self.handle_game_flag(p),
+            ScoreDetailedCTF(p) => self.handle_score_detailed_ctf(p),
+            GameFirewall(p) => self.handle_game_firewall(p),
+            GamePlayersAlive(p) => self.handle_game_players_alive(p),
+            ScoreDetailedBTR(p) => self.handle_score_detailed_btr(p),
+            ServerCustom(p) => self.handle_server_custom(p),
             _ => (),
         }
+
+        std::mem::replace(&mut self.events, vec![])
     }
 
     pub fn update(&mut self, now: Instant) {
@@ -102,10 +170,22 @@ impl World {
 
         for player in self.players.values_mut() {
             Self::update_player(player, delta);
+            Self::expire_powerup(player, now);
+        }
+
+        if let Some(expiry) = self.me.powerup_expiry {
+            if expiry <= now {
+                self.me.powerup_expiry = None;
+            }
         }
 
-        for mob in self.mobs.values_mut() {
-            Self::update_mob(mob, delta);
+        // Missiles that hit terrain are gone as far as
+        // we're concerned, even if the server hasn't
+        // told us yet.
+        self.mobs.retain(|_, mob| Self::update_mob(mob, delta));
+
+        if let Some(ref mut firewall) = self.btr.firewall {
+            firewall.update(delta);
         }
     }
 }
@@ -118,13 +198,23 @@ where
     (a % b + b) % b
 }
 
+/// Size of a missile for terrain collisions.
+pub(crate) const MISSILE_RADIUS: f32 = 4.0;
+
 /// Frame update details
 impl World {
-    fn update_player(player: &mut Player, delta: Time) {
+    pub(crate) fn update_player(player: &mut Player, delta: Time) {
         use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
         #[allow(non_upper_case_globals)]
         const PIx2: f32 = PI * 2.0;
 
+        player.energy = Energy::new(
+            (player.energy.inner() + player.energy_regen.inner() * delta.inner()).min(1.0),
+        );
+        player.health = Health::new(
+            (player.health.inner() + player.health_regen.inner() * delta.inner()).min(1.0),
+        );
+
         let mut movement_angle = None;
         let info = &CONFIG.planes[player.plane];
         let boost_factor = match player.keystate.boost {
@@ -206,6 +296,8 @@ impl World {
         player.pos += oldspeed * delta + (player.vel - oldspeed) * delta * 0.5;
         player.rot = fmod(player.rot, PIx2.into());
 
+        Self::bounce_player(player, info.hit_radius.inner());
+
         let bound = Position::new(16352.0, 8160.0);
 
         if player.pos.x.abs() > bound.x {
@@ -215,7 +307,43 @@ impl World {
             player.pos.y = player.pos.y.signum() * bound.y
         }
     }
-    fn update_mob(mob: &mut Mob, delta: Time) {
+    /// Bounce off of any hill that the player is overlapping.
+    ///
+    /// Like the server, this sends the plane directly away
+    /// from the centre of the hill while keeping its speed
+    /// (with a minimum so that it can't get stuck). It
+    /// also moves the plane back out to the edge of the
+    /// hill since we only find out about the collision
+    /// a frame late.
+    fn bounce_player(player: &mut Player, radius: f32) {
+        let (x, y) = (player.pos.x.inner(), player.pos.y.inner());
+        let hill = match DEFAULT_TERRAIN.collision(x, y, radius) {
+            Some(hill) => *hill,
+            None => return,
+        };
+
+        let (dx, dy) = (x - hill.x, y - hill.y);
+        let len = (dx * dx + dy * dy).sqrt().max(0.001);
+        let (nx, ny) = (dx / len, dy / len);
+        let speed = player.vel.length().inner().max(1.0);
+        let dist = hill.radius + radius;
+
+        player.vel = Velocity::new(nx * speed, ny * speed);
+        player.pos = Position::new(hill.x + nx * dist, hill.y + ny * dist);
+    }
+    /// Mark a powerup as expired without forgetting it.
+    /// The flags in `upgrades` are left for the server to
+    /// clear, since it may still think the powerup is on.
+    fn expire_powerup(player: &mut Player, now: Instant) {
+        if let Some(ref mut powerup) = player.powerup {
+            if powerup.expiry <= now {
+                powerup.expired = true;
+            }
+        }
+    }
+    /// Move a mob forward by `delta`. Returns `false` if
+    /// the mob hit terrain and should be removed.
+    pub(crate) fn update_mob(mob: &mut Mob, delta: Time) -> bool {
         const BOUNDARY_X: f32 = 16384.0;
         const BOUNDARY_Y: f32 = BOUNDARY_X / 2.0;
         const SIZE_X: f32 = BOUNDARY_X * 2.0;
@@ -243,6 +371,14 @@ impl World {
         if mob.pos.y > BOUNDARY_Y.into() {
             mob.pos.y -= SIZE_Y.into()
         }
+
+        // Only missiles move so only they can hit anything
+        if !mob.missile() {
+            return true;
+        }
+
+        let (x, y) = (mob.pos.x.inner(), mob.pos.y.inner());
+        DEFAULT_TERRAIN.collision(x, y, MISSILE_RADIUS).is_none()
     }
 }
 
@@ -254,9 +390,13 @@ impl World {
         } else {
             warn_unknown_player!(PlayerUpdate, update.id);
         }
+
+        if update.id.0 == self.me.id && update.keystate.strafe {
+            self.confirm_special();
+        }
     }
     fn handle_player_new(&mut self, packet: &PlayerNew) {
-        let new = Player {
+        let mut new = Player {
             id: packet.id.into(),
             name: packet.name.clone(),
             status: packet.status,
@@ -269,17 +409,30 @@ impl World {
             rot: packet.rot,
             ..Default::default()
         };
+        new.update_powerup(CONFIG.shield_duration);
 
-        if let Some(_old) = self.players.insert(packet.id.into(), new) {
-            warn_unknown_player!(PlayerNew, packet.id);
+        let old = self.players.insert(packet.id.into(), new);
+        if let Some(ref old) = old {
+            self.names.remove(&old.name);
         }
         self.names.insert(packet.name.clone(), packet.id.into());
+
+        // A player we already know about hasn't joined
+        if old.is_some() {
+            warn_unknown_player!(PlayerNew, packet.id);
+        } else {
+            self.events.push(GameEvent::PlayerJoined(packet.id.into()));
+        }
     }
     fn handle_player_leave(&mut self, packet: &PlayerLeave) {
         let removed = self.players.remove(&packet.id.into());
 
         if let Some(player) = removed {
             self.names.remove(&player.name);
+            self.events.push(GameEvent::PlayerLeft {
+                id: player.id,
+                name: player.name,
+            });
         } else {
             warn_unknown_player!(PlayerLeave, packet.id);
         }
@@ -301,6 +454,12 @@ impl World {
         if let Some(player) = self.players.get_mut(&packet.id.into()) {
             player.status = PlayerStatus::Dead;
             player.pos = packet.pos;
+
+            self.events.push(GameEvent::PlayerKilled {
+                victim: packet.id.into(),
+                killer: packet.killer.map(Into::into),
+                pos: packet.pos,
+            });
         } else {
             warn_unknown_player!(PlayerKill, packet.id);
         }
@@ -313,11 +472,28 @@ impl World {
         }
     }
     fn handle_player_powerup(&mut self, packet: &PlayerPowerup) {
-        self.me.powerup_expiry =
-            Some(Instant::now() + Duration::from_millis(packet.duration.into()));
+        let duration = Duration::from_millis(packet.duration.into());
+        let expiry = Instant::now() + duration;
+        self.me.powerup_expiry = Some(expiry);
+        self.events.push(GameEvent::PowerupGained {
+            player: self.me.id,
+            ty: packet.ty,
+            duration,
+        });
+
+        if let Some(player) = self.players.get_mut(&self.me.id) {
+            // This packet has the exact duration, unlike the
+            // guess made from the flags in PlayerUpdate.
+            player.powerup = Some(Powerup {
+                ty: packet.ty,
+                expiry,
+                expired: false,
+            });
 
-        if let Some(_player) = self.players.get_mut(&self.me.id) {
-            // FIXME: This should probably set some state
+            match packet.ty {
+                PowerupType::Shield => player.upgrades.shield = true,
+                PowerupType::Inferno => player.upgrades.inferno = true,
+            }
         } else {
             error!("The current player doesn't exist (id: {})", self.me.id);
         }
@@ -327,10 +503,16 @@ impl World {
             player.pos = packet.pos;
             player.rot = packet.rot;
             player.upgrades = packet.upgrades;
+            // Any shield here is the spawn shield
+            player.powerup = None;
+            player.update_powerup(CONFIG.spawn_shield_duration);
 
             if packet.id.0 == self.me.id {
                 self.get_me_mut().keystate = ServerKeyState::default();
+                self.me.special = SpecialState::default();
             }
+
+            self.events.push(GameEvent::PlayerRespawned(packet.id.into()));
         } else {
             warn_unknown_player!(PlayerRespawn, packet.id);
         }
@@ -338,6 +520,14 @@ impl World {
     fn handle_player_reteam(&mut self, packet: &PlayerReteam) {
         for data in packet.players.iter() {
             if let Some(player) = self.players.get_mut(&data.id.into()) {
+                if player.team != data.team {
+                    self.events.push(GameEvent::TeamChanged {
+                        player: player.id,
+                        old: player.team,
+                        new: data.team,
+                    });
+                }
+
                 player.team = data.team;
             } else {
                 warn_unknown_player!(PlayerReteam, data.id);
@@ -370,6 +560,18 @@ impl World {
             warn_unknown_player!(PlayerFire, packet.id);
         }
 
+        if packet.id.0 == self.me.id {
+            self.me.last_fire = Some(Instant::now());
+
+            let triple = packet
+                .projectiles
+                .iter()
+                .any(|p| p.ty == MobType::TornadoTripleMissile);
+            if triple {
+                self.confirm_special();
+            }
+        }
+
         for projectile in packet.projectiles.iter() {
             let mob = Mob {
                 id: projectile.id.into(),
@@ -387,6 +589,12 @@ impl World {
                     mob.id
                 );
             }
+
+            self.events.push(GameEvent::MissileFired {
+                owner: packet.id.into(),
+                mob: projectile.id.into(),
+                ty: projectile.ty,
+            });
         }
     }
     fn handle_player_flag(&mut self, packet: &PlayerFlag) {
@@ -405,6 +613,8 @@ impl World {
         };
         self.game_ty = packet.ty;
         self.room = packet.room.clone();
+        self.ctf = CtfState::default();
+        self.btr = BtrState::default();
 
         self.players = packet
             .players
@@ -415,7 +625,7 @@ impl World {
                     x => Some(x),
                 };
 
-                let details = Player {
+                let mut details = Player {
                     level,
                     id: player.id.into(),
                     status: player.status.into(),
@@ -428,6 +638,7 @@ impl World {
                     upgrades: player.upgrades,
                     ..Default::default()
                 };
+                details.update_powerup(CONFIG.shield_duration);
 
                 (details.id, details)
             })
@@ -465,6 +676,16 @@ impl World {
         } else {
             warn_unknown_player!(ScoreUpdate, packet.id);
         }
+
+        if packet.id.0 == self.me.id {
+            let gained = packet.upgrades > self.me.upgrades.unused;
+            self.me.upgrades.unused = packet.upgrades;
+
+            if gained {
+                self.events
+                    .push(GameEvent::UpgradePointsGained(packet.upgrades));
+            }
+        }
     }
     fn handle_ping_result(&mut self, packet: &PingResult) {
         self.ping = packet.ping;
@@ -536,6 +757,14 @@ impl World {
         } else {
             warn_unknown_player!(EventBounce, evt.id);
         }
+
+        if evt.id.0 == self.me.id {
+            self.me.special.boosting = evt.boost;
+
+            if evt.boost {
+                self.confirm_special();
+            }
+        }
     }
     fn handle_event_bounce(&mut self, evt: &EventBounce) {
         self.clock = evt.clock;
@@ -583,6 +812,10 @@ impl World {
             warn_unknown_player!(EventRepel, evt.id);
         }
 
+        if evt.id.0 == self.me.id {
+            self.confirm_special();
+        }
+
         for data in evt.players.iter() {
             if let Some(player) = self.players.get_mut(&data.id.into()) {
                 player.pos = data.pos;
@@ -625,9 +858,486 @@ impl World {
         } else {
             warn_unknown_player!(EventStealth, evt.id);
         }
+
+        if evt.id.0 == self.me.id {
+            self.me.special.stealthed = evt.state;
+            self.confirm_special();
+        }
+    }
+
+    /// The server did something that can only have
+    /// been caused by us using our special.
+    fn confirm_special(&mut self) {
+        self.me.special.pending = None;
+        self.me.special.last_activated = Some(Instant::now());
+    }
+
+    fn push_chat(&mut self, kind: ChatKind, from: u16, to: Option<u16>, text: &str) {
+        let msg = ChatMessage {
+            kind,
+            from,
+            to,
+            text: text.to_owned(),
+            time: Instant::now(),
+        };
+
+        if self.chat.len() >= CHAT_HISTORY_LEN {
+            self.chat.pop_front();
+        }
+        self.chat.push_back(msg.clone());
+        self.events.push(GameEvent::Chat(msg));
+    }
+    fn handle_chat_public(&mut self, packet: &ChatPublic) {
+        self.push_chat(ChatKind::Public, packet.id.into(), None, &packet.text);
+    }
+    fn handle_chat_team(&mut self, packet: &ChatTeam) {
+        self.push_chat(ChatKind::Team, packet.id.into(), None, &packet.text);
+    }
+    fn handle_chat_whisper(&mut self, packet: &ChatWhisper) {
+        self.push_chat(
+            ChatKind::Whisper,
+            packet.from.into(),
+            Some(packet.to.into()),
+            &packet.text,
+        );
+    }
+    fn handle_chat_say(&mut self, packet: &ChatSay) {
+        self.push_chat(ChatKind::Say, packet.id.into(), None, &packet.text);
+    }
+    fn handle_chat_vote_mute_passed(&mut self, packet: &ChatVoteMutePassed) {
+        if let Some(player) = self.players.get_mut(&packet.id.into()) {
+            player.muted = true;
+            self.events.push(GameEvent::PlayerMuted(packet.id.into()));
+        } else {
+            warn_unknown_player!(ChatVoteMutePassed, packet.id);
+        }
+    }
+
+    fn handle_game_flag(&mut self, packet: &GameFlag) {
+        let team = packet.flag.0;
+        let (old_blue, old_red) = (self.ctf.blue_score, self.ctf.red_score);
+
+        self.ctf.blue_score = packet.blueteam;
+        self.ctf.red_score = packet.redteam;
+
+        // A capture is scored by the team that doesn't own the flag
+        let captured = match team {
+            BLUE_TEAM => packet.redteam > old_red,
+            RED_TEAM => packet.blueteam > old_blue,
+            _ => false,
+        };
+
+        let flag = match self.ctf.flag_mut(team) {
+            Some(flag) => flag,
+            None => {
+                warn!("Received GameFlag for unknown team {}", team.0);
+                return;
+            }
+        };
+
+        let prev = *flag;
+        let prev_carrier = prev.carrier;
+        flag.pos = packet.pos;
+        flag.carrier = packet.id.map(Into::into);
+        flag.seen = true;
+
+        // The server also sends the flag's position when we
+        // join and every so often after that, so a flag at
+        // its base is only a return if it was away from it.
+        let returned = prev.seen
+            && is_flag_base(team, packet.pos)
+            && (prev_carrier.is_some() || !is_flag_base(team, prev.pos));
+
+        let event = match packet.ty {
+            FlagUpdateType::Carrier => match flag.carrier {
+                Some(carrier) if prev_carrier != Some(carrier) => {
+                    Some(GameEvent::FlagTaken { team, carrier })
+                }
+                _ => None,
+            },
+            FlagUpdateType::Position if captured => Some(GameEvent::FlagCaptured {
+                team,
+                carrier: prev_carrier,
+            }),
+            FlagUpdateType::Position if returned => Some(GameEvent::FlagReturned { team }),
+            FlagUpdateType::Position if prev_carrier.is_some() => Some(GameEvent::FlagDropped {
+                team,
+                pos: packet.pos,
+            }),
+            FlagUpdateType::Position => None,
+        };
+
+        self.events.extend(event);
+    }
+    fn handle_score_detailed_ctf(&mut self, packet: &ScoreDetailedCTF) {
+        for entry in packet.scores.iter() {
+            if let Some(player) = self.players.get_mut(&entry.id.into()) {
+                player.score = entry.score.into();
+                player.captures = entry.captures.into();
+                player.kills = entry.kills.into();
+                player.deaths = entry.deaths.into();
+                player.level = match entry.level.0 {
+                    0 => None,
+                    x => Some(x),
+                };
+            } else {
+                warn_unknown_player!(ScoreDetailedCTF, entry.id);
+            }
+        }
+    }
+    fn handle_game_firewall(&mut self, packet: &GameFirewall) {
+        // Protocol 0.3 only knows about status and type 0,
+        // and packets with any other value fail to decode,
+        // so every firewall update that reaches us is taken
+        // to describe the current safe zone.
+        self.btr.firewall = Some(Firewall {
+            pos: packet.pos,
+            radius: packet.radius,
+            speed: packet.speed,
+        });
+    }
+    fn handle_game_players_alive(&mut self, packet: &GamePlayersAlive) {
+        self.btr.players_alive = packet.players;
+    }
+    fn handle_score_detailed_btr(&mut self, packet: &ScoreDetailedBTR) {
+        for entry in packet.scores.iter() {
+            if let Some(player) = self.players.get_mut(&entry.id.into()) {
+                player.score = entry.score.into();
+                player.kills = entry.kills.into();
+                player.deaths = entry.deaths.into();
+                player.level = match entry.level.0 {
+                    0 => None,
+                    x => Some(x),
+                };
+
+                if !entry.alive {
+                    player.status = PlayerStatus::Dead;
+                }
+
+                self.btr.wins.insert(player.id, entry.wins);
+            } else {
+                warn_unknown_player!(ScoreDetailedBTR, entry.id);
+            }
+        }
+    }
+    fn handle_server_custom(&mut self, packet: &ServerCustom) {
+        use crate::protocol::custom::{BTRData, CTFData};
+
+        match packet.ty {
+            ServerCustomType::CTFWin => match serde_json::from_str::<CTFData>(&packet.data) {
+                Ok(data) => {
+                    self.ctf.winner = Some(data.winner);
+                    self.events.push(GameEvent::CtfGameOver {
+                        winner: data.winner,
+                    });
+                }
+                Err(e) => warn!("Received invalid CTFWin data {:?}: {}", packet.data, e),
+            },
+            ServerCustomType::BTRWin => match serde_json::from_str::<BTRData>(&packet.data) {
+                Ok(data) => {
+                    self.btr.winner = Some(data.player.clone());
+                    self.events.push(GameEvent::BtrGameOver {
+                        winner: data.player,
+                    });
+                }
+                Err(e) => warn!("Received invalid BTRWin data {:?}: {}", packet.data, e),
+            },
+        }
     }
 
     fn handle_error(&mut self, evt: &Error) {
         error!("{:?}", evt.error);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::TERRAIN;
+
+    fn flag_position(pos: Position) -> ServerPacket {
+        GameFlag {
+            ty: FlagUpdateType::Position,
+            flag: Flag(BLUE_TEAM),
+            id: None,
+            pos,
+            blueteam: 0,
+            redteam: 0,
+        }
+        .into()
+    }
+
+    fn returned(events: &[GameEvent]) -> bool {
+        events.iter().any(|evt| match evt {
+            GameEvent::FlagReturned { .. } => true,
+            _ => false,
+        })
+    }
+
+    #[test]
+    fn flag_at_base_is_not_returned() {
+        let mut world = World::default();
+        let base = flag_base(BLUE_TEAM).unwrap();
+
+        // Sent when joining and then periodically
+        assert!(!returned(&world.handle_packet(&flag_position(base))));
+        assert!(!returned(&world.handle_packet(&flag_position(base))));
+    }
+
+    #[test]
+    fn dropped_flag_is_returned() {
+        let mut world = World::default();
+        let base = flag_base(BLUE_TEAM).unwrap();
+
+        world.handle_packet(&flag_position(Position::new(0.0, 0.0)));
+        assert!(returned(&world.handle_packet(&flag_position(base))));
+    }
+
+    #[test]
+    fn firewall_limits_safe_zone() {
+        let mut world = World::default();
+        world.game_ty = GameType::BTR;
+        assert!(world.is_inside_safe_zone(Position::new(5000.0, 0.0)));
+
+        world.handle_packet(
+            &GameFirewall {
+                ty: FirewallUpdateType::_Unknown,
+                status: FirewallStatus::_Unknown,
+                pos: Position::new(0.0, 0.0),
+                radius: 1000.0,
+                speed: 0.0,
+            }
+            .into(),
+        );
+
+        assert_eq!(world.btr.firewall.unwrap().radius, 1000.0);
+        assert!(world.is_inside_safe_zone(Position::new(500.0, 0.0)));
+        assert!(!world.is_inside_safe_zone(Position::new(5000.0, 0.0)));
+    }
+
+    fn player_new(id: u16, team: u16) -> ServerPacket {
+        PlayerNew {
+            id: crate::protocol::Player(id),
+            status: PlayerStatus::Alive,
+            name: format!("player{}", id),
+            ty: PlaneType::Predator,
+            team: Team(team),
+            pos: Position::new(0.0, 0.0),
+            rot: Rotation::new(0.0),
+            flag: FlagCode::default(),
+            upgrades: Upgrades::default(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn player_joined_once() {
+        let mut world = World::default();
+
+        match &world.handle_packet(&player_new(2, 1))[..] {
+            [GameEvent::PlayerJoined(2)] => (),
+            events => panic!("Unexpected events {:?}", events),
+        }
+
+        // A duplicate PlayerNew replaces the player but
+        // they didn't join again.
+        assert!(world.handle_packet(&player_new(2, 1)).is_empty());
+        assert_eq!(world.players.len(), 1);
+    }
+
+    #[test]
+    fn player_killed() {
+        let mut world = World::default();
+        world.handle_packet(&player_new(2, 1));
+        world.handle_packet(&player_new(3, 2));
+
+        let events = world.handle_packet(
+            &PlayerKill {
+                id: crate::protocol::Player(2),
+                killer: Some(crate::protocol::Player(3)),
+                pos: Position::new(100.0, 200.0),
+            }
+            .into(),
+        );
+
+        match &events[..] {
+            [GameEvent::PlayerKilled {
+                victim: 2,
+                killer: Some(3),
+                pos,
+            }] => assert_eq!(*pos, Position::new(100.0, 200.0)),
+            events => panic!("Unexpected events {:?}", events),
+        }
+        assert_eq!(world.players[&2].status, PlayerStatus::Dead);
+    }
+
+    #[test]
+    fn team_changed() {
+        let mut world = World::default();
+        world.handle_packet(&player_new(2, 1));
+        world.handle_packet(&player_new(3, 2));
+
+        let reteam = |players: &[(u16, u16)]| -> ServerPacket {
+            PlayerReteam {
+                players: players
+                    .iter()
+                    .map(|&(id, team)| PlayerReteamPlayer {
+                        id: crate::protocol::Player(id),
+                        team: Team(team),
+                    })
+                    .collect(),
+            }
+            .into()
+        };
+
+        // Only players whose team actually changed
+        match &world.handle_packet(&reteam(&[(2, 2), (3, 2)]))[..] {
+            [GameEvent::TeamChanged {
+                player: 2,
+                old: Team(1),
+                new: Team(2),
+            }] => (),
+            events => panic!("Unexpected events {:?}", events),
+        }
+        assert_eq!(world.players[&2].team, Team(2));
+    }
+
+    #[test]
+    fn missile_fired() {
+        let mut world = World::default();
+        world.handle_packet(&player_new(2, 1));
+
+        let projectile = |id| PlayerFireProjectile {
+            id: crate::protocol::Mob(id),
+            ty: MobType::PredatorMissile,
+            pos: Position::new(0.0, 0.0),
+            speed: Velocity::new(0.0, -5.0),
+            accel: Accel::new(0.0, -0.1),
+            max_speed: Speed::new(9.0),
+        };
+        let events = world.handle_packet(
+            &PlayerFire {
+                clock: 0,
+                id: crate::protocol::Player(2),
+                energy: Energy::new(0.5),
+                energy_regen: EnergyRegen::new(0.0),
+                projectiles: vec![projectile(10), projectile(11)],
+            }
+            .into(),
+        );
+
+        match &events[..] {
+            [GameEvent::MissileFired {
+                owner: 2,
+                mob: 10,
+                ty: MobType::PredatorMissile,
+            }, GameEvent::MissileFired {
+                owner: 2, mob: 11, ..
+            }] => (),
+            events => panic!("Unexpected events {:?}", events),
+        }
+        assert_eq!(world.mobs[&10].owner, Some(2));
+        assert!(world.mobs.contains_key(&11));
+    }
+
+    #[test]
+    fn player_respawned() {
+        let mut world = World::default();
+        world.handle_packet(&player_new(2, 1));
+
+        let respawn = |id| -> ServerPacket {
+            PlayerRespawn {
+                id: crate::protocol::Player(id),
+                pos: Position::new(-500.0, 300.0),
+                rot: Rotation::new(1.0),
+                upgrades: Upgrades::default(),
+            }
+            .into()
+        };
+
+        // Unknown players don't respawn
+        assert!(world.handle_packet(&respawn(5)).is_empty());
+
+        match &world.handle_packet(&respawn(2))[..] {
+            [GameEvent::PlayerRespawned(2)] => (),
+            events => panic!("Unexpected events {:?}", events),
+        }
+        assert_eq!(world.players[&2].pos, Position::new(-500.0, 300.0));
+    }
+
+    #[test]
+    fn bounce_pushes_player_out_of_hill() {
+        let [x, y, r] = TERRAIN[0];
+        let (x, y, r) = (x as f32, y as f32, r as f32);
+
+        // Just inside the left edge and flying into the hill
+        let mut player = Player::default();
+        player.pos = Position::new(x - r - 2.0, y);
+        player.vel = Velocity::new(2.0, 0.0);
+
+        World::bounce_player(&mut player, 10.0);
+
+        assert_eq!(player.pos, Position::new(x - r - 10.0, y));
+        assert_eq!(player.vel, Velocity::new(-2.0, 0.0));
+    }
+
+    #[test]
+    fn bounce_ignores_open_space() {
+        let mut player = Player::default();
+        player.vel = Velocity::new(2.0, 0.0);
+
+        World::bounce_player(&mut player, 10.0);
+
+        assert_eq!(player.pos, Position::new(0.0, 0.0));
+        assert_eq!(player.vel, Velocity::new(2.0, 0.0));
+    }
+
+    #[test]
+    fn missile_hitting_terrain_is_removed() {
+        let [x, y, r] = TERRAIN[0];
+        let (x, y, r) = (x as f32, y as f32, r as f32);
+        let missile = |id, pos| Mob {
+            id,
+            ty: MobType::PredatorMissile,
+            pos,
+            vel: Velocity::new(5.0, 0.0),
+            accel: Accel::new(0.0, 0.0),
+            max_speed: Speed::new(10.0),
+            owner: None,
+        };
+
+        let mut world = World::default();
+        // One heading into the hill and one in the open
+        let edge = Position::new(x - r - 6.0, y);
+        world.mobs.insert(1, missile(1, edge));
+        world.mobs.insert(2, missile(2, Position::new(0.0, 0.0)));
+
+        let now = Instant::now();
+        world.update(now);
+        assert_eq!(world.mobs.len(), 2);
+
+        world.update(now + Duration::from_millis(50));
+        assert!(!world.mobs.contains_key(&1));
+        assert!(world.mobs.contains_key(&2));
+    }
+
+    #[test]
+    fn expired_shield_is_not_rearmed() {
+        let mut player = Player::default();
+        player.upgrades.shield = true;
+        player.update_powerup(CONFIG.shield_duration);
+
+        let expiry = player.powerup.unwrap().expiry;
+        World::expire_powerup(&mut player, expiry);
+        assert!(!player.has_shield());
+
+        // The server hasn't cleared the flag yet
+        player.update_powerup(CONFIG.shield_duration);
+        assert!(!player.has_shield());
+        assert_eq!(player.powerup.unwrap().expiry, expiry);
+
+        player.upgrades.shield = false;
+        player.update_powerup(CONFIG.shield_duration);
+        assert!(player.powerup.is_none());
+    }
+}
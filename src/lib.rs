@@ -8,20 +8,34 @@ pub extern crate airmash_protocol as protocol;
 extern crate airmash_protocol_v5 as protocol_v5;
 extern crate futures;
 extern crate hashbrown;
+extern crate serde_json;
 extern crate tokio;
 extern crate tokio_tls;
 extern crate tokio_tungstenite;
 extern crate tungstenite;
 extern crate url;
 
+mod behaviour;
 mod client;
 mod config;
 mod future;
 mod game;
 mod macros;
-//mod map;
+mod packet_name;
+mod record;
 
+pub mod action;
 pub mod consts;
+pub mod loadtest;
+pub mod map;
+#[cfg(any(test, feature = "test-server"))]
+pub mod test_server;
 
+pub use self::action::{
+    Action, ActionContext, ActionId, ActionOutput, ActionSet, ActionStatus, KeyArbiter,
+};
+pub use self::behaviour::*;
 pub use self::client::*;
 pub use self::game::*;
+pub use self::packet_name::*;
+pub use self::record::*;
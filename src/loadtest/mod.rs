@@ -0,0 +1,292 @@
+//! Stress testing a server with many connections.
+//!
+//! [`run_load_test`] opens a number of connections
+//! (one at a time, with a delay in between), logs them
+//! all in, drives them with an [`InputPattern`] and
+//! collects statistics about what the server sent back.
+//! Once the test is over these are returned as a
+//! [`LoadTestReport`].
+
+mod report;
+
+pub use self::report::{ConnectionStats, LoadTestReport};
+
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::r#await;
+use tokio::timer::Delay;
+use url::Url;
+
+use crate::protocol::*;
+use crate::{ClientBase, ClientEvent, ClientKeyState, ClientResult, ReconnectPolicy};
+
+/// How often to check whether all the connections
+/// have finished.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long to wait for connections to finish after the
+/// test is over before giving up on them.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// The keys that each connection holds down during
+/// a load test.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InputPattern {
+    /// Don't press anything.
+    Idle,
+    /// Fly around in circles.
+    Circle,
+    /// Fly forward, switching between turning left and
+    /// right with the given period.
+    Zigzag(Duration),
+    /// Fly around in circles while firing constantly.
+    Firing,
+}
+
+impl InputPattern {
+    /// The keys that should be held down `elapsed` after
+    /// the connection logged in.
+    pub fn keys(&self, elapsed: Duration) -> ClientKeyState {
+        let mut keys = ClientKeyState::default();
+
+        match *self {
+            InputPattern::Idle => (),
+            InputPattern::Circle => {
+                keys.up = true;
+                keys.left = true;
+            }
+            InputPattern::Zigzag(period) => {
+                let period = millis(period).max(1);
+                let left = (millis(elapsed) / period) % 2 == 0;
+
+                keys.up = true;
+                keys.left = left;
+                keys.right = !left;
+            }
+            InputPattern::Firing => {
+                keys.up = true;
+                keys.left = true;
+                keys.fire = true;
+            }
+        }
+
+        keys
+    }
+}
+
+/// Settings for [`run_load_test`].
+#[derive(Clone, Debug)]
+pub struct LoadTestConfig {
+    pub server: Url,
+    /// The number of connections to open.
+    pub connections: usize,
+    /// The delay between opening each connection.
+    pub ramp_interval: Duration,
+    /// How long to keep going once all the connections
+    /// have been opened.
+    pub duration: Duration,
+    /// Each connection logs in with this name followed
+    /// by its index.
+    pub name: String,
+    pub flag: String,
+    pub pattern: InputPattern,
+    /// Reconnect dropped connections instead of leaving
+    /// them closed for the rest of the test.
+    pub reconnect: Option<ReconnectPolicy>,
+}
+
+impl LoadTestConfig {
+    pub fn new(server: Url) -> Self {
+        Self {
+            server,
+            connections: 10,
+            ramp_interval: Duration::from_millis(100),
+            duration: Duration::from_secs(60),
+            name: "LOADTEST".to_owned(),
+            flag: "UN".to_owned(),
+            pattern: InputPattern::Circle,
+            reconnect: None,
+        }
+    }
+}
+
+fn millis(dur: Duration) -> u64 {
+    dur.as_secs() * 1000 + dur.subsec_millis() as u64
+}
+
+async fn run_connection_inner(
+    index: usize,
+    config: &LoadTestConfig,
+    deadline: Instant,
+    stats: &mut ConnectionStats,
+) -> ClientResult<()> {
+    use self::KeyCode::*;
+
+    let start = Instant::now();
+    let mut client = match config.server.scheme() {
+        "wss" => r#await!(ClientBase::new_insecure(config.server.clone()))?,
+        _ => r#await!(ClientBase::new(config.server.clone()))?,
+    };
+    stats.connect = Some(start.elapsed());
+
+    if let Some(policy) = config.reconnect {
+        client.set_reconnect_policy(policy);
+    }
+
+    let login = Instant::now();
+    r#await!(client.send(client::Login {
+        flag: config.flag.clone(),
+        horizon_x: 3000,
+        horizon_y: 3000,
+        name: format!("{}{}", config.name, index),
+        protocol: 5,
+        session: "none".to_owned(),
+    }))?;
+
+    let mut logged_in = None;
+    while let Some(evt) = r#await!(client.next())? {
+        match evt {
+            ClientEvent::Packet(packet) => {
+                stats.record_packet(&packet);
+
+                match packet {
+                    ServerPacket::Login(_) if logged_in.is_none() => {
+                        stats.login = Some(login.elapsed());
+                        logged_in = Some(Instant::now());
+                    }
+                    ServerPacket::PingResult(p) => stats.pings.push(p.ping),
+                    _ => (),
+                }
+            }
+            ClientEvent::Frame(now) => {
+                if now >= deadline {
+                    return Ok(());
+                }
+
+                let time = match logged_in {
+                    Some(time) => time,
+                    None => continue,
+                };
+                let keys = config.pattern.keys(now - time);
+
+                for &key in [Up, Down, Left, Right, Fire, Special].iter() {
+                    if client.world().keys.get(key) != keys.get(key) {
+                        r#await!(client.send_key(key, keys.get(key)))?;
+                    }
+                }
+            }
+            ClientEvent::Disconnected(e) => stats.disconnects.push(e.to_string()),
+            _ => (),
+        }
+    }
+
+    // The stream only ends early if the server closed
+    // the connection on us.
+    stats.disconnects.push("Connection closed".to_owned());
+
+    Ok(())
+}
+
+async fn run_connection(
+    index: usize,
+    config: Arc<LoadTestConfig>,
+    deadline: Instant,
+    results: Arc<Mutex<Vec<ConnectionStats>>>,
+) {
+    let start = Instant::now();
+    let mut stats = ConnectionStats::new(index);
+
+    if let Err(e) = r#await!(run_connection_inner(index, &config, deadline, &mut stats)) {
+        warn!("Load test connection {} failed: {}", index, e);
+        stats.disconnects.push(e.to_string());
+    }
+
+    stats.active = start.elapsed();
+    results.lock().unwrap().push(stats);
+}
+
+/// Run a load test against a server and collect the
+/// results once it is done.
+///
+/// The connections are spawned onto the tokio runtime, so
+/// this must be run from within one.
+pub async fn run_load_test(config: LoadTestConfig) -> LoadTestReport {
+    let config = Arc::new(config);
+    let results = Arc::new(Mutex::new(Vec::with_capacity(config.connections)));
+
+    let start = Instant::now();
+    let deadline = start + config.ramp_interval * config.connections as u32 + config.duration;
+
+    for i in 0..config.connections {
+        tokio::spawn_async(run_connection(
+            i,
+            config.clone(),
+            deadline,
+            results.clone(),
+        ));
+
+        let _ = r#await!(Delay::new(Instant::now() + config.ramp_interval));
+    }
+
+    info!("Opened {} connections", config.connections);
+
+    while results.lock().unwrap().len() < config.connections {
+        if Instant::now() > deadline + SHUTDOWN_GRACE {
+            warn!("Gave up waiting for some connections to finish");
+            break;
+        }
+
+        let _ = r#await!(Delay::new(Instant::now() + POLL_INTERVAL));
+    }
+
+    let mut connections = mem::replace(&mut *results.lock().unwrap(), vec![]);
+
+    // Anything that still hasn't finished is most likely
+    // stuck connecting to the server.
+    for i in 0..config.connections {
+        if !connections.iter().any(|c| c.index == i) {
+            let mut stats = ConnectionStats::new(i);
+            stats.disconnects.push("Did not finish".to_owned());
+            connections.push(stats);
+        }
+    }
+    connections.sort_by_key(|c| c.index);
+
+    LoadTestReport {
+        duration: start.elapsed(),
+        connections,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idle_presses_nothing() {
+        let keys = InputPattern::Idle.keys(Duration::from_secs(5));
+
+        assert_eq!(keys, ClientKeyState::default());
+    }
+
+    #[test]
+    fn zigzag_alternates() {
+        let pattern = InputPattern::Zigzag(Duration::from_millis(500));
+
+        let first = pattern.keys(Duration::from_millis(100));
+        assert!(first.up && first.left && !first.right);
+
+        let second = pattern.keys(Duration::from_millis(600));
+        assert!(second.up && !second.left && second.right);
+
+        assert_eq!(pattern.keys(Duration::from_millis(1100)), first);
+    }
+
+    #[test]
+    fn firing_holds_fire() {
+        let keys = InputPattern::Firing.keys(Duration::from_secs(0));
+
+        assert!(keys.fire && keys.up);
+    }
+}
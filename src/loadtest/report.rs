@@ -0,0 +1,317 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crate::packet_name::server_packet_name;
+use crate::protocol::ServerPacket;
+
+/// Everything that was measured for a single connection.
+#[derive(Clone, Debug)]
+pub struct ConnectionStats {
+    pub index: usize,
+    /// How long it took to open the websocket.
+    pub connect: Option<Duration>,
+    /// Time between sending the login packet and the
+    /// server's login response.
+    pub login: Option<Duration>,
+    /// Every ping that the server reported, in ms.
+    pub pings: Vec<u16>,
+    /// The number of packets received of each type.
+    pub packets: BTreeMap<&'static str, u64>,
+    /// Why the connection dropped, for every time
+    /// that it did.
+    pub disconnects: Vec<String>,
+    /// How long the connection was running for.
+    pub active: Duration,
+}
+
+impl ConnectionStats {
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            connect: None,
+            login: None,
+            pings: vec![],
+            packets: BTreeMap::new(),
+            disconnects: vec![],
+            active: Duration::from_secs(0),
+        }
+    }
+
+    pub(crate) fn record_packet(&mut self, packet: &ServerPacket) {
+        *self.packets.entry(server_packet_name(packet)).or_insert(0) += 1;
+    }
+
+    pub fn total_packets(&self) -> u64 {
+        self.packets.values().sum()
+    }
+
+    /// The number of packets of each type received per
+    /// second while the connection was running.
+    pub fn packet_rates(&self) -> BTreeMap<&'static str, f64> {
+        let active = secs(self.active).max(1e-3);
+
+        self.packets
+            .iter()
+            .map(|(&name, &count)| (name, count as f64 / active))
+            .collect()
+    }
+
+    pub fn mean_ping(&self) -> Option<f64> {
+        if self.pings.is_empty() {
+            return None;
+        }
+
+        Some(self.pings.iter().map(|&x| x as f64).sum::<f64>() / self.pings.len() as f64)
+    }
+}
+
+/// The results of a load test.
+#[derive(Clone, Debug)]
+pub struct LoadTestReport {
+    /// How long the whole test took.
+    pub duration: Duration,
+    pub connections: Vec<ConnectionStats>,
+}
+
+fn secs(dur: Duration) -> f64 {
+    dur.as_secs() as f64 + dur.subsec_nanos() as f64 * 1e-9
+}
+
+fn ms(dur: Duration) -> f64 {
+    secs(dur) * 1000.0
+}
+
+/// Format the minimum, mean and maximum of some
+/// measurements in ms.
+fn spread<I: Iterator<Item = f64>>(values: I) -> String {
+    let values: Vec<f64> = values.collect();
+
+    if values.is_empty() {
+        return "n/a".to_owned();
+    }
+
+    let min = values.iter().cloned().fold(std::f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(std::f64::NEG_INFINITY, f64::max);
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+
+    format!("min {:.1}ms, mean {:.1}ms, max {:.1}ms", min, mean, max)
+}
+
+/// Quote a CSV field if it needs it.
+fn csv_field(s: &str) -> String {
+    if s.contains(|c: char| c == ',' || c == '"' || c == '\n') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+fn csv_opt(value: Option<f64>) -> String {
+    value.map(|x| format!("{:.1}", x)).unwrap_or_default()
+}
+
+impl LoadTestReport {
+    /// The number of connections that managed to open
+    /// a websocket.
+    pub fn connected(&self) -> usize {
+        self.connections.iter().filter(|c| c.connect.is_some()).count()
+    }
+
+    /// The number of connections that managed to log in.
+    pub fn logged_in(&self) -> usize {
+        self.connections.iter().filter(|c| c.login.is_some()).count()
+    }
+
+    /// The mean number of packets per second that each
+    /// connection received of each type, busiest first.
+    ///
+    /// Each connection's rate is taken over the time that
+    /// it was running, and connections that never ran are
+    /// left out.
+    pub fn packet_rates(&self) -> Vec<(&'static str, f64)> {
+        let active: Vec<_> = self
+            .connections
+            .iter()
+            .filter(|c| c.active > Duration::from_secs(0))
+            .collect();
+
+        let mut totals = BTreeMap::new();
+        for conn in active.iter() {
+            for (name, rate) in conn.packet_rates() {
+                *totals.entry(name).or_insert(0.0) += rate;
+            }
+        }
+
+        let count = active.len().max(1) as f64;
+        let mut rates: Vec<_> = totals
+            .into_iter()
+            .map(|(name, total)| (name, total / count))
+            .collect();
+        rates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        rates
+    }
+
+    /// How often each disconnect reason came up, most
+    /// common first.
+    pub fn disconnect_reasons(&self) -> Vec<(String, usize)> {
+        let mut counts = BTreeMap::new();
+        for reason in self.connections.iter().flat_map(|c| c.disconnects.iter()) {
+            *counts.entry(reason.clone()).or_insert(0) += 1;
+        }
+
+        let mut reasons: Vec<_> = counts.into_iter().collect();
+        reasons.sort_by(|a, b| b.1.cmp(&a.1));
+        reasons
+    }
+
+    /// Write one row per connection, with a column for
+    /// each packet type that was seen.
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let mut names: Vec<&'static str> = self
+            .connections
+            .iter()
+            .flat_map(|c| c.packets.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+
+        write!(
+            out,
+            concat!(
+                "index,connect_ms,login_ms,ping_mean_ms,ping_max_ms,",
+                "active_s,packets,packets_per_s,disconnects"
+            )
+        )?;
+        for name in names.iter() {
+            write!(out, ",{}", name)?;
+        }
+        writeln!(out)?;
+
+        for conn in self.connections.iter() {
+            write!(
+                out,
+                "{},{},{},{},{},{:.1},{},{:.1},{}",
+                conn.index,
+                csv_opt(conn.connect.map(ms)),
+                csv_opt(conn.login.map(ms)),
+                csv_opt(conn.mean_ping()),
+                csv_opt(conn.pings.iter().max().map(|&x| x as f64)),
+                secs(conn.active),
+                conn.total_packets(),
+                conn.packet_rates().values().sum::<f64>(),
+                csv_field(&conn.disconnects.join("; "))
+            )?;
+            for name in names.iter() {
+                write!(out, ",{}", conn.packets.get(name).cloned().unwrap_or(0))?;
+            }
+            writeln!(out)?;
+        }
+
+        out.flush()
+    }
+}
+
+impl Display for LoadTestReport {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        writeln!(fmt, "Load test finished in {:.1}s", secs(self.duration))?;
+        writeln!(
+            fmt,
+            "Connections: {} opened, {} connected, {} logged in",
+            self.connections.len(),
+            self.connected(),
+            self.logged_in()
+        )?;
+        writeln!(
+            fmt,
+            "Connect latency: {}",
+            spread(self.connections.iter().filter_map(|c| c.connect).map(ms))
+        )?;
+        writeln!(
+            fmt,
+            "Login latency: {}",
+            spread(self.connections.iter().filter_map(|c| c.login).map(ms))
+        )?;
+        writeln!(
+            fmt,
+            "Ping: {}",
+            spread(
+                self.connections
+                    .iter()
+                    .flat_map(|c| c.pings.iter())
+                    .map(|&x| x as f64)
+            )
+        )?;
+
+        writeln!(fmt, "Packets per second, per connection:")?;
+        for (name, rate) in self.packet_rates() {
+            writeln!(fmt, "    {:<24}{:.1}", name, rate)?;
+        }
+
+        let reasons = self.disconnect_reasons();
+        if reasons.is_empty() {
+            writeln!(fmt, "No disconnects")?;
+        } else {
+            writeln!(fmt, "Disconnects:")?;
+            for (reason, count) in reasons {
+                writeln!(fmt, "    {:>5} {}", count, reason)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> LoadTestReport {
+        let mut conn = ConnectionStats::new(0);
+        conn.connect = Some(Duration::from_millis(12));
+        conn.login = Some(Duration::from_millis(30));
+        conn.pings = vec![10, 30];
+        conn.packets.insert("Ping", 4);
+        conn.packets.insert("PlayerUpdate", 6);
+        conn.disconnects.push("Closed, by server".to_owned());
+        conn.active = Duration::from_secs(2);
+
+        let mut stuck = ConnectionStats::new(1);
+        stuck.disconnects.push("Did not finish".to_owned());
+
+        LoadTestReport {
+            duration: Duration::from_secs(3),
+            connections: vec![conn, stuck],
+        }
+    }
+
+    #[test]
+    fn csv_output() {
+        let mut out = vec![];
+        report().write_csv(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                concat!(
+                    "index,connect_ms,login_ms,ping_mean_ms,ping_max_ms,",
+                    "active_s,packets,packets_per_s,disconnects,Ping,PlayerUpdate"
+                ),
+                "0,12.0,30.0,20.0,30.0,2.0,10,5.0,\"Closed, by server\",4,6",
+                "1,,,,,0.0,0,0.0,Did not finish,0,0",
+            ]
+        );
+    }
+
+    #[test]
+    fn rates_skip_inactive_connections() {
+        assert_eq!(
+            report().packet_rates(),
+            vec![("PlayerUpdate", 3.0), ("Ping", 2.0)]
+        );
+    }
+}
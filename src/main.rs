@@ -1,6 +1,7 @@
 #![feature(futures_api, await_macro, async_await)]
 
 extern crate airmash_client;
+extern crate clap;
 #[macro_use]
 extern crate log;
 extern crate env_logger;
@@ -8,55 +9,185 @@ extern crate rand;
 extern crate tokio;
 extern crate url;
 
+use airmash_client::action::{Evade, Fire, Follow};
+use airmash_client::loadtest::{self, InputPattern, LoadTestConfig};
 use airmash_client::protocol::*;
 use airmash_client::*;
 
+use std::cmp::Ordering;
 use std::env;
 use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
 use std::time::{Duration, Instant};
 
 use tokio::r#await;
 use url::Url;
 
-async fn single_bot_inner(name: String, server: Url, i: u64) -> Result<(), Box<Error + 'static>> {
-    //use self::ClientEvent::*;
+/// What each bot does once it has logged in.
+#[derive(Clone, Debug)]
+enum Behaviour {
+    /// Sit at spawn and do nothing.
+    Idle,
+    /// Follow the player with the given name.
+    Follow(String),
+    /// Fly between the waypoints in order, forever.
+    Patrol(Vec<Position>),
+    /// Chase and shoot the closest enemy while dodging
+    /// missiles.
+    Hunter,
+}
+
+/// What the binary was asked to do.
+enum Mode {
+    /// Launch a fleet of bots.
+    Bots(Args),
+    /// Run a load test, optionally writing the results
+    /// to a CSV file.
+    LoadTest(LoadTestConfig, Option<String>),
+}
+
+#[derive(Clone, Debug)]
+struct Args {
+    pub server: Url,
+    pub count: u64,
+    pub name: String,
+    pub flags: Vec<String>,
+    pub stagger: Duration,
+    pub behaviour: Behaviour,
+}
+
+/// How close an enemy needs to be before a hunter will
+/// start shooting at them.
+const HUNTER_RANGE: f32 = 600.0;
+
+/// Replace `{}` in the name pattern with the bot index,
+/// or append the index if there is no `{}`.
+fn bot_name(pattern: &str, i: u64) -> String {
+    if pattern.contains("{}") {
+        pattern.replace("{}", &i.to_string())
+    } else {
+        format!("{}{}", pattern, i)
+    }
+}
+
+async fn idle(client: &mut ClientBase) -> ClientResult<()> {
+    while r#await!(client.next())?.is_some() {}
+
+    Ok(())
+}
+
+async fn follow(client: &mut ClientBase, target: &str) -> ClientResult<()> {
+    while r#await!(client.next())?.is_some() {
+        // Wait around for the target to show up instead
+        // of leaving, they may just not have joined yet.
+        let id = match client.world().names.get(target) {
+            Some(x) => *x,
+            None => continue,
+        };
+
+        r#await!(client.follow(id))?;
+    }
+
+    Ok(())
+}
+
+async fn patrol(client: &mut ClientBase, points: &[Position]) -> ClientResult<()> {
+    if points.is_empty() {
+        return r#await!(idle(client));
+    }
+
+    for &pos in points.iter().cycle() {
+        if !r#await!(client.navigate_to(pos))? {
+            warn!("No path to {}, {}", pos.x.inner(), pos.y.inner());
+            r#await!(client.wait(Duration::from_secs(1)))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Find the closest living enemy that we can see.
+fn closest_enemy(world: &World) -> Option<u16> {
+    let me = world.get_me();
+
+    world
+        .players
+        .values()
+        .filter(|p| p.id != me.id && p.team != me.team)
+        .filter(|p| p.visible && !p.is_spec && p.status == PlayerStatus::Alive)
+        .map(|p| (p.id, (p.pos - me.pos).length().inner()))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+        .map(|(id, _)| id)
+}
+
+async fn hunter(client: &mut ClientBase) -> ClientResult<()> {
+    let mut actions = ActionSet::new();
+    let mut chase: Option<(u16, ActionId)> = None;
+    let mut shot: Option<ActionId> = None;
+
+    actions.add(10, Evade);
+
+    while let Some(evt) = r#await!(client.next())? {
+        let target = closest_enemy(client.world());
+
+        // Switch targets whenever someone closer shows up
+        // or the current target is gone.
+        if chase.map(|(id, _)| id) != target {
+            if let Some((_, action)) = chase.take() {
+                actions.cancel(action);
+            }
+            if let Some(id) = target {
+                chase = Some((id, actions.add(0, Follow::new(id))));
+            }
+        }
+
+        if let Some(id) = target {
+            let dist = (client.world().players[&id].pos - client.world().get_me().pos)
+                .length()
+                .inner();
+            let firing = shot.map(|x| actions.contains(x)).unwrap_or(false);
+
+            if dist < HUNTER_RANGE && !firing {
+                shot = Some(actions.add(1, Fire::at(id)));
+            }
+        }
 
-    let mut client = r#await!(Client::new_insecure(server))?;
+        r#await!(client.tick_actions(&mut actions, &evt))?;
+    }
+
+    Ok(())
+}
 
-    r#await!(client.wait(Duration::from_millis(100 * i)))?;
+async fn single_bot_inner(
+    name: String,
+    flag: String,
+    server: Url,
+    behaviour: Behaviour,
+) -> Result<(), Box<Error + 'static>> {
+    let mut client = match server.scheme() {
+        "wss" => r#await!(Client::new_insecure(server))?,
+        _ => r#await!(Client::new(server))?,
+    };
+    client.set_reconnect_policy(ReconnectPolicy::default());
 
     r#await!(client.send(client::Login {
-        flag: "ca".to_owned(),
+        flag,
         horizon_x: 3000,
         horizon_y: 3000,
-        name: name,
+        name,
         protocol: 5,
         session: "none".to_owned()
     }))?;
 
-    // Should probably have a wait-for-login command
-    r#await!(client.wait(Duration::from_secs(5)))?;
-    //r#await!(client.send(client::Command{
-    //    com: "respawn".to_owned(),
-    //    data: "2".to_owned()
-    //}))?;
-
-    //let mut next = Instant::now() + Duration::from_secs(10);
-
-    //r#await!(client.point_at(Position::new(5000.0, 5000.0)))?;
+    r#await!(client.wait_for_login())?;
+    //r#await!(client.respawn(PlaneType::Goliath))?;
 
-    while let Some(_) = r#await!(client.next())? {
-        let player = match client.world().get_me().team.0 {
-            1 => "STEAMROLLER",
-            _ => "STEAMROLLER",
-        };
-
-        let id = match client.world().names.get(player) {
-            Some(x) => *x,
-            None => break,
-        };
-        r#await!(client.follow(id))?;
-        r#await!(client.say("FOR GONDOR!".to_string()))?;
+    match behaviour {
+        Behaviour::Idle => r#await!(idle(&mut client))?,
+        Behaviour::Follow(target) => r#await!(follow(&mut client, &target))?,
+        Behaviour::Patrol(points) => r#await!(patrol(&mut client, &points))?,
+        Behaviour::Hunter => r#await!(hunter(&mut client))?,
     }
 
     info!("Shutting down bot {}", client.world().get_me().name);
@@ -64,45 +195,274 @@ async fn single_bot_inner(name: String, server: Url, i: u64) -> Result<(), Box<E
     Ok(())
 }
 
-async fn single_bot(name: String, server: Url, i: u64) {
-    match r#await!(single_bot_inner(name, server, i)) {
+async fn single_bot(name: String, flag: String, server: Url, behaviour: Behaviour) {
+    match r#await!(single_bot_inner(name.clone(), flag, server, behaviour)) {
         Ok(_) => (),
         Err(e) => {
-            error!("The bot ended with an error {}", e);
+            error!("Bot {} ended with an error {}", name, e);
         }
     }
 }
 
-async fn spawn_bots(name: String, url: Url) {
-    for i in 0..2 {
-        tokio::spawn_async(single_bot(format!("{}{}", name, i), url.clone(), i));
-        r#await!(tokio::timer::Delay::new(
-            Instant::now() + Duration::from_millis(100)
-        ))
-        .unwrap();
+async fn spawn_bots(args: Args) {
+    for i in 0..args.count {
+        let flag = args.flags[i as usize % args.flags.len()].clone();
+
+        tokio::spawn_async(single_bot(
+            bot_name(&args.name, i),
+            flag,
+            args.server.clone(),
+            args.behaviour.clone(),
+        ));
+        r#await!(tokio::timer::Delay::new(Instant::now() + args.stagger)).unwrap();
     }
 }
 
-fn run_bot(name: &str, server: &str) -> Result<(), Box<Error>> {
-    env::set_var("RUST_BACKTRACE", "1");
+async fn load_test(config: LoadTestConfig, csv: Option<String>) {
+    let report = r#await!(loadtest::run_load_test(config));
 
-    let name = name.to_owned();
-    let url: Url = server.parse()?;
+    println!("{}", report);
 
-    tokio::run_async(spawn_bots(name, url));
+    if let Some(path) = csv {
+        let result = File::create(&path).and_then(|f| report.write_csv(BufWriter::new(f)));
 
-    Ok(())
+        match result {
+            Ok(()) => println!("Wrote per-connection results to {}", path),
+            Err(e) => eprintln!("Failed to write {}: {}", path, e),
+        }
+    }
 }
 
-const SERVER: &'static str = "wss://game.airmash.steamroller.tk/ffa";
-//const SERVER: &'static str = "wss://game-us-s1.airma.sh/ffa2";
-//const SERVER: &'static str = "ws://localhost:3501";
+fn parse_pos(s: &str) -> Result<Position, String> {
+    let parts: Vec<_> = s.split(',').map(|x| x.trim().parse::<f32>()).collect();
+
+    match parts.as_slice() {
+        [Ok(x), Ok(y)] => Ok(Position::new(*x, *y)),
+        _ => Err(format!("Expected a position of the form x,y but got {}", s)),
+    }
+}
+
+fn parse_args() -> Result<Mode, String> {
+    use clap::*;
+
+    let args = App::new("AIRMASH Bots")
+        .about("Launches a fleet of bots against a server")
+        .author("STEAMROLLER")
+        .arg(
+            Arg::with_name("server")
+                .long("server")
+                .short("s")
+                .help("The server that the bots will connect to.")
+                .default_value("wss://game.airmash.steamroller.tk/ffa")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("count")
+                .long("count")
+                .short("c")
+                .help("How many bots to launch.")
+                .default_value("2")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("name")
+                .long("name")
+                .short("n")
+                .help("The bot names. Any {} is replaced with the bot's index, otherwise the index is appended.")
+                .default_value("TESTBOT")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("flag")
+                .long("flag")
+                .short("f")
+                .help("The flags that the bots will use, as a comma separated list. Bots cycle through them in order.")
+                .default_value("ca")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("stagger")
+                .long("stagger")
+                .help("How long to wait between launching each bot, in milliseconds.")
+                .default_value("100")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("behaviour")
+                .long("behaviour")
+                .short("b")
+                .help("What the bots do once they have logged in.")
+                .possible_values(&["idle", "follow", "patrol", "hunter"])
+                .default_value("follow")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("target")
+                .long("target")
+                .short("t")
+                .help("The player name to follow with the follow behaviour.")
+                .default_value("STEAMROLLER")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("waypoint")
+                .long("waypoint")
+                .short("w")
+                .help("A waypoint for the patrol behaviour, as x,y. Can be given multiple times.")
+                .allow_hyphen_values(true)
+                .multiple(true)
+                .number_of_values(1)
+                .takes_value(true),
+        )
+        .subcommand(
+            SubCommand::with_name("loadtest")
+                .about("Stress a server with many connections and report on how it held up. Uses the server, name and flag given before the subcommand.")
+                .arg(
+                    Arg::with_name("connections")
+                        .long("connections")
+                        .short("c")
+                        .help("How many connections to open.")
+                        .default_value("10")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("ramp")
+                        .long("ramp")
+                        .help("How long to wait between opening each connection, in milliseconds.")
+                        .default_value("100")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("duration")
+                        .long("duration")
+                        .short("d")
+                        .help("How long to keep running once all connections are open, in seconds.")
+                        .default_value("60")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("pattern")
+                        .long("pattern")
+                        .short("p")
+                        .help("The keys that each connection presses.")
+                        .possible_values(&["idle", "circle", "zigzag", "firing"])
+                        .default_value("circle")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("csv")
+                        .long("csv")
+                        .help("Write per-connection results to this CSV file.")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("reconnect")
+                        .long("reconnect")
+                        .help("Reconnect dropped connections instead of leaving them closed."),
+                ),
+        )
+        .get_matches();
+
+    // All of these have default values so they should
+    // always be present.
+    let server = args.value_of("server").expect("No server provided!");
+    let count = args.value_of("count").expect("No count provided!");
+    let name = args.value_of("name").expect("No name provided!");
+    let flags = args.value_of("flag").expect("No flag provided!");
+    let stagger = args.value_of("stagger").expect("No stagger provided!");
+
+    let server = server
+        .parse::<Url>()
+        .map_err(|e| format!("An error occurred while parsing the server URL:\n{}", e))?;
+    let count = count
+        .parse::<u64>()
+        .map_err(|e| format!("Invalid bot count {}: {}", count, e))?;
+    let stagger = stagger
+        .parse::<u64>()
+        .map_err(|e| format!("Invalid stagger {}: {}", stagger, e))?;
+    let flags: Vec<String> = flags.split(',').map(|x| x.trim().to_owned()).collect();
+
+    if let Some(args) = args.subcommand_matches("loadtest") {
+        let connections = args.value_of("connections").expect("No connections provided!");
+        let ramp = args.value_of("ramp").expect("No ramp provided!");
+        let duration = args.value_of("duration").expect("No duration provided!");
+
+        let connections = connections
+            .parse::<usize>()
+            .map_err(|e| format!("Invalid connection count {}: {}", connections, e))?;
+        let ramp = ramp
+            .parse::<u64>()
+            .map_err(|e| format!("Invalid ramp {}: {}", ramp, e))?;
+        let duration = duration
+            .parse::<u64>()
+            .map_err(|e| format!("Invalid duration {}: {}", duration, e))?;
+
+        let mut config = LoadTestConfig::new(server);
+        config.connections = connections;
+        config.ramp_interval = Duration::from_millis(ramp);
+        config.duration = Duration::from_secs(duration);
+        config.name = name.to_owned();
+        config.flag = flags[0].clone();
+        config.pattern = match args.value_of("pattern") {
+            Some("idle") => InputPattern::Idle,
+            Some("zigzag") => InputPattern::Zigzag(Duration::from_secs(1)),
+            Some("firing") => InputPattern::Firing,
+            _ => InputPattern::Circle,
+        };
+        if args.is_present("reconnect") {
+            config.reconnect = Some(ReconnectPolicy::default());
+        }
+
+        let csv = args.value_of("csv").map(|x| x.to_owned());
+
+        return Ok(Mode::LoadTest(config, csv));
+    }
+
+    let behaviour = match args.value_of("behaviour") {
+        Some("idle") => Behaviour::Idle,
+        Some("patrol") => {
+            let points = args
+                .values_of("waypoint")
+                .map(|vals| vals.map(parse_pos).collect::<Result<Vec<_>, _>>())
+                .unwrap_or_else(|| Ok(vec![]))?;
+
+            if points.is_empty() {
+                return Err("The patrol behaviour needs at least one waypoint".into());
+            }
+
+            Behaviour::Patrol(points)
+        }
+        Some("hunter") => Behaviour::Hunter,
+        _ => Behaviour::Follow(args.value_of("target").unwrap_or("STEAMROLLER").to_owned()),
+    };
+
+    Ok(Mode::Bots(Args {
+        server,
+        count,
+        name: name.to_owned(),
+        flags,
+        stagger: Duration::from_millis(stagger),
+        behaviour,
+    }))
+}
 
 fn main() {
-    env::set_var("RUST_LOG", "info");
+    if env::var("RUST_LOG").is_err() {
+        env::set_var("RUST_LOG", "info");
+    }
+    env::set_var("RUST_BACKTRACE", "1");
     env_logger::init();
 
-    if let Err(e) = run_bot("TESTBOT", SERVER) {
-        println!("An error occurred:\n{}", e);
+    let mode = match parse_args() {
+        Ok(v) => v,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return;
+        }
+    };
+
+    match mode {
+        Mode::Bots(args) => tokio::run_async(spawn_bots(args)),
+        Mode::LoadTest(config, csv) => tokio::run_async(load_test(config, csv)),
     }
 }
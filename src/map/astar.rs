@@ -0,0 +1,233 @@
+//! A* search over the coarse terrain grid.
+//!
+//! This is less precise than the visibility graph
+//! since every grid cell touched by a hill is treated
+//! as solid, but it doesn't need any setup.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use airmash_protocol::Position;
+
+use super::grid::MAP;
+
+const GRID_WIDTH: i32 = 512;
+const GRID_HEIGHT: i32 = 256;
+const CELL_SIZE: f32 = 64.0;
+const MAP_OFFSET_X: f32 = 16384.0;
+const MAP_OFFSET_Y: f32 = 8192.0;
+
+/// Costs are in tenths of a cell so that they can be
+/// kept as integers in the heap.
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+fn to_cell(pos: Position) -> (i32, i32) {
+    let x = ((pos.x.inner() + MAP_OFFSET_X) / CELL_SIZE) as i32;
+    let y = ((pos.y.inner() + MAP_OFFSET_Y) / CELL_SIZE) as i32;
+
+    (x.max(0).min(GRID_WIDTH - 1), y.max(0).min(GRID_HEIGHT - 1))
+}
+
+fn cell_centre((x, y): (i32, i32)) -> Position {
+    Position::new(
+        (x as f32 + 0.5) * CELL_SIZE - MAP_OFFSET_X,
+        (y as f32 + 0.5) * CELL_SIZE - MAP_OFFSET_Y,
+    )
+}
+
+fn blocked((x, y): (i32, i32)) -> bool {
+    if x < 0 || y < 0 || x >= GRID_WIDTH || y >= GRID_HEIGHT {
+        return true;
+    }
+
+    MAP[y as usize][x as usize] != 0
+}
+
+fn cell_index((x, y): (i32, i32)) -> usize {
+    (y * GRID_WIDTH + x) as usize
+}
+
+/// Octile distance, which is exact for a grid with
+/// diagonal moves and no obstacles.
+fn heuristic(a: (i32, i32), b: (i32, i32)) -> u32 {
+    let dx = (a.0 - b.0).abs() as u32;
+    let dy = (a.1 - b.1).abs() as u32;
+
+    STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+}
+
+/// Whether a plane can fly in a straight line between
+/// two points without crossing a blocked grid cell.
+pub fn grid_line_of_sight(from: Position, to: Position) -> bool {
+    let delta = to - from;
+    let dist = delta.length().inner();
+    // Sample at a quarter cell so that we can't skip
+    // over the corner of a cell.
+    let steps = (dist / (CELL_SIZE * 0.25)).ceil().max(1.0) as usize;
+    let start = to_cell(from);
+
+    (0..=steps).all(|i| {
+        let t = i as f32 / steps as f32;
+        let cell = to_cell(from + delta * t);
+
+        // Don't get stuck if the grid thinks that we're
+        // already inside a hill.
+        cell == start || !blocked(cell)
+    })
+}
+
+/// Plan a path between two points on the map using A*
+/// over the terrain grid in [`MAP`].
+///
+/// The raw grid path is smoothed by skipping any
+/// waypoints that can be bypassed in a straight line,
+/// so the result only contains the corners of the path
+/// followed by `goal` itself. The start position is
+/// not included.
+///
+/// Returns `None` if the goal is inside terrain or
+/// cannot be reached.
+pub fn find_grid_path(start: Position, goal: Position) -> Option<Vec<Position>> {
+    const NEIGHBOURS: [(i32, i32, u32); 8] = [
+        (1, 0, STRAIGHT_COST),
+        (-1, 0, STRAIGHT_COST),
+        (0, 1, STRAIGHT_COST),
+        (0, -1, STRAIGHT_COST),
+        (1, 1, DIAGONAL_COST),
+        (1, -1, DIAGONAL_COST),
+        (-1, 1, DIAGONAL_COST),
+        (-1, -1, DIAGONAL_COST),
+    ];
+
+    let start_cell = to_cell(start);
+    let goal_cell = to_cell(goal);
+
+    if blocked(goal_cell) {
+        return None;
+    }
+    if grid_line_of_sight(start, goal) {
+        return Some(vec![goal]);
+    }
+
+    let size = (GRID_WIDTH * GRID_HEIGHT) as usize;
+    let mut cost = vec![u32::max_value(); size];
+    let mut parent = vec![usize::max_value(); size];
+    let mut open = BinaryHeap::new();
+
+    cost[cell_index(start_cell)] = 0;
+    open.push(Reverse((heuristic(start_cell, goal_cell), 0, start_cell)));
+
+    while let Some(Reverse((_, g, cell))) = open.pop() {
+        if cell == goal_cell {
+            break;
+        }
+        if g > cost[cell_index(cell)] {
+            continue;
+        }
+
+        for &(dx, dy, step) in NEIGHBOURS.iter() {
+            let next = (cell.0 + dx, cell.1 + dy);
+
+            if blocked(next) {
+                continue;
+            }
+            // Don't cut the corners of blocked cells
+            if dx != 0 && dy != 0 {
+                if blocked((cell.0 + dx, cell.1)) || blocked((cell.0, cell.1 + dy)) {
+                    continue;
+                }
+            }
+
+            let next_cost = g + step;
+            let idx = cell_index(next);
+            if next_cost < cost[idx] {
+                cost[idx] = next_cost;
+                parent[idx] = cell_index(cell);
+                open.push(Reverse((next_cost + heuristic(next, goal_cell), next_cost, next)));
+            }
+        }
+    }
+
+    if cost[cell_index(goal_cell)] == u32::max_value() {
+        return None;
+    }
+
+    let mut cells = vec![];
+    let mut idx = cell_index(goal_cell);
+    while idx != cell_index(start_cell) {
+        let cell = (idx as i32 % GRID_WIDTH, idx as i32 / GRID_WIDTH);
+        cells.push(cell_centre(cell));
+        idx = parent[idx];
+    }
+    cells.reverse();
+
+    // The last cell centre is replaced by the goal itself.
+    cells.pop();
+    cells.push(goal);
+
+    Some(smooth_path(start, cells))
+}
+
+/// Remove every waypoint that can be skipped by flying
+/// straight from the previous one to the one after.
+fn smooth_path(start: Position, path: Vec<Position>) -> Vec<Position> {
+    let mut smoothed = vec![];
+    let mut current = start;
+    let mut i = 0;
+
+    while i < path.len() {
+        // Find the furthest waypoint that is directly visible
+        let mut furthest = i;
+        for j in (i + 1..path.len()).rev() {
+            if grid_line_of_sight(current, path[j]) {
+                furthest = j;
+                break;
+            }
+        }
+
+        current = path[furthest];
+        smoothed.push(current);
+        i = furthest + 1;
+    }
+
+    smoothed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_around_terrain() {
+        let start = Position::new(-10000.0, 0.0);
+        let goal = Position::new(10000.0, 0.0);
+        assert!(!grid_line_of_sight(start, goal));
+
+        let path = find_grid_path(start, goal).unwrap();
+        assert!(path.len() > 1);
+        assert_eq!(*path.last().unwrap(), goal);
+
+        let mut current = start;
+        for &waypoint in &path {
+            assert!(grid_line_of_sight(current, waypoint));
+            current = waypoint;
+        }
+    }
+
+    #[test]
+    fn straight_path_in_open_space() {
+        let start = Position::new(-10000.0, 0.0);
+        let goal = Position::new(-9900.0, 0.0);
+
+        assert_eq!(find_grid_path(start, goal), Some(vec![goal]));
+    }
+
+    #[test]
+    fn goal_inside_terrain() {
+        let goal = cell_centre(to_cell(Position::new(-3296.0, -7648.0)));
+
+        assert!(blocked(to_cell(goal)));
+        assert_eq!(find_grid_path(Position::new(-10000.0, 0.0), goal), None);
+    }
+}
@@ -0,0 +1,50 @@
+//! The terrain of the default map and path planning
+//! around it.
+//!
+//! There are two planners here. [`find_path`] uses a
+//! visibility graph built from the terrain circles and
+//! gives smooth, near-optimal paths. [`find_grid_path`]
+//! runs A* over the coarse obstacle grid generated by
+//! `scripts/gen_grid.rs` and is mostly kept around for
+//! comparison.
+
+use airmash_protocol::Position;
+use lazy_static::lazy_static;
+
+mod astar;
+mod grid;
+mod terrain;
+mod terrain_data;
+mod visibility;
+
+pub use self::astar::{find_grid_path, grid_line_of_sight};
+pub use self::grid::MAP;
+pub use self::terrain::{Circle, Terrain};
+pub use self::terrain_data::TERRAIN;
+pub use self::visibility::VisibilityGraph;
+
+/// How far paths from [`find_path`] stay away from
+/// hills. This is a bit larger than the biggest plane.
+pub const DEFAULT_CLEARANCE: f32 = 60.0;
+
+lazy_static! {
+    /// The terrain of the default map.
+    pub static ref DEFAULT_TERRAIN: Terrain = Terrain::default();
+
+    /// The visibility graph for the default map.
+    pub static ref GRAPH: VisibilityGraph =
+        VisibilityGraph::new(&DEFAULT_TERRAIN, DEFAULT_CLEARANCE);
+}
+
+/// Find a path between two points on the default map.
+///
+/// See [`VisibilityGraph::find_path`] for details.
+pub fn find_path(start: Position, goal: Position) -> Option<Vec<Position>> {
+    GRAPH.find_path(start, goal)
+}
+
+/// Whether a plane can fly straight between the two
+/// points without coming too close to a hill.
+pub fn line_of_sight(from: Position, to: Position) -> bool {
+    GRAPH.terrain().line_of_sight(from, to)
+}
@@ -0,0 +1,178 @@
+//! The terrain of the default AIRMASH map as a list of
+//! circles.
+
+use airmash_protocol::Position;
+
+use super::terrain_data::TERRAIN;
+
+/// Size of the buckets in the spatial hash.
+const BUCKET_SIZE: f32 = 512.0;
+const MAP_WIDTH: f32 = 32768.0;
+const MAP_HEIGHT: f32 = 16384.0;
+const BUCKETS_X: usize = (MAP_WIDTH / BUCKET_SIZE) as usize;
+const BUCKETS_Y: usize = (MAP_HEIGHT / BUCKET_SIZE) as usize;
+
+/// A single hill.
+#[derive(Copy, Clone, Debug)]
+pub struct Circle {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+}
+
+impl Circle {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        let (dx, dy) = (x - self.x, y - self.y);
+
+        dx * dx + dy * dy < self.radius * self.radius
+    }
+
+    /// Whether the segment from `a` to `b` passes through
+    /// the inside of this circle. Segments that only touch
+    /// the edge don't count.
+    pub fn intersects_segment(&self, a: (f32, f32), b: (f32, f32)) -> bool {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let len2 = dx * dx + dy * dy;
+
+        let t = if len2 == 0.0 {
+            0.0
+        } else {
+            (((self.x - a.0) * dx + (self.y - a.1) * dy) / len2)
+                .max(0.0)
+                .min(1.0)
+        };
+
+        let (px, py) = (a.0 + dx * t - self.x, a.1 + dy * t - self.y);
+        // Leave a little slack so that segments tangent to
+        // the circle aren't counted.
+        let r = self.radius - 0.01;
+
+        px * px + py * py < r * r
+    }
+}
+
+/// A set of terrain circles with a spatial hash so that
+/// collision queries only need to look at nearby hills.
+#[derive(Clone, Debug)]
+pub struct Terrain {
+    circles: Vec<Circle>,
+    buckets: Vec<Vec<usize>>,
+}
+
+impl Terrain {
+    pub fn new(circles: Vec<Circle>) -> Self {
+        let mut buckets = vec![vec![]; BUCKETS_X * BUCKETS_Y];
+
+        for (i, c) in circles.iter().enumerate() {
+            let (x0, y0) = bucket(c.x - c.radius, c.y - c.radius);
+            let (x1, y1) = bucket(c.x + c.radius, c.y + c.radius);
+
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    buckets[y * BUCKETS_X + x].push(i);
+                }
+            }
+        }
+
+        Self { circles, buckets }
+    }
+
+    /// The same terrain with every circle grown by
+    /// `by`. This is used to keep a plane's hitbox
+    /// away from the hills.
+    pub fn inflated(&self, by: f32) -> Self {
+        Self::new(
+            self.circles
+                .iter()
+                .map(|c| Circle {
+                    radius: c.radius + by,
+                    ..*c
+                })
+                .collect(),
+        )
+    }
+
+    pub fn circles(&self) -> &[Circle] {
+        &self.circles
+    }
+
+    /// All circles that may overlap the given rectangle.
+    /// The same circle may be returned more than once.
+    pub fn nearby<'a>(
+        &'a self,
+        min: (f32, f32),
+        max: (f32, f32),
+    ) -> impl Iterator<Item = &'a Circle> + 'a {
+        let (x0, y0) = bucket(min.0, min.1);
+        let (x1, y1) = bucket(max.0, max.1);
+
+        (y0..=y1)
+            .flat_map(move |y| (x0..=x1).map(move |x| y * BUCKETS_X + x))
+            .flat_map(move |b| self.buckets[b].iter())
+            .map(move |&i| &self.circles[i])
+    }
+
+    /// The first hill that overlaps a circle with the
+    /// given centre and radius.
+    pub fn collision(&self, x: f32, y: f32, radius: f32) -> Option<&Circle> {
+        self.nearby((x - radius, y - radius), (x + radius, y + radius))
+            .find(|c| {
+                let (dx, dy) = (x - c.x, y - c.y);
+                let r = c.radius + radius;
+
+                dx * dx + dy * dy < r * r
+            })
+    }
+
+    /// Whether the point is inside any hill.
+    pub fn is_blocked(&self, pos: Position) -> bool {
+        let (x, y) = (pos.x.inner(), pos.y.inner());
+
+        self.nearby((x, y), (x, y)).any(|c| c.contains(x, y))
+    }
+
+    /// Whether a straight line between the two points
+    /// avoids all hills.
+    ///
+    /// Hills that contain either end point are ignored
+    /// so that a plane that has been pushed into one
+    /// can still find its way out.
+    pub fn line_of_sight(&self, from: Position, to: Position) -> bool {
+        let a = (from.x.inner(), from.y.inner());
+        let b = (to.x.inner(), to.y.inner());
+
+        self.segment_clear(a, b)
+    }
+
+    pub(crate) fn segment_clear(&self, a: (f32, f32), b: (f32, f32)) -> bool {
+        let min = (a.0.min(b.0), a.1.min(b.1));
+        let max = (a.0.max(b.0), a.1.max(b.1));
+
+        !self.nearby(min, max).any(|c| {
+            c.intersects_segment(a, b) && !c.contains(a.0, a.1) && !c.contains(b.0, b.1)
+        })
+    }
+}
+
+impl Default for Terrain {
+    /// The terrain of the default map.
+    fn default() -> Self {
+        Self::new(
+            TERRAIN
+                .iter()
+                .map(|c| Circle {
+                    x: c[0] as f32,
+                    y: c[1] as f32,
+                    radius: c[2] as f32,
+                })
+                .collect(),
+        )
+    }
+}
+
+fn bucket(x: f32, y: f32) -> (usize, usize) {
+    let bx = ((x + MAP_WIDTH / 2.0) / BUCKET_SIZE).max(0.0) as usize;
+    let by = ((y + MAP_HEIGHT / 2.0) / BUCKET_SIZE).max(0.0) as usize;
+
+    (bx.min(BUCKETS_X - 1), by.min(BUCKETS_Y - 1))
+}
@@ -0,0 +1,528 @@
+// The hills of the default AIRMASH map.
+//
+// `scripts/gen_grid.rs` includes this file directly, so
+// it should only ever contain the table.
+
+/// Each entry is `[x, y, radius]`.
+pub const TERRAIN: [[i16; 3]; 520] = [
+    [1009, -2308, 108],
+    [1241, -2490, 60],
+    [1157, -2379, 84],
+    [622, -2126, 48],
+    [669, -2187, 72],
+    [-392, -1669, 132],
+    [-273, -1746, 60],
+    [-252, -1504, 120],
+    [1553, -2016, 48],
+    [1637, -1972, 60],
+    [1736, -1922, 60],
+    [2150, -2406, 72],
+    [2238, -2318, 108],
+    [2364, -2391, 72],
+    [2491, -2682, 72],
+    [2596, -2671, 108],
+    [-150, -3147, 48],
+    [-155, -3044, 84],
+    [-427, -3600, 48],
+    [-259, -2982, 60],
+    [-379, -3529, 72],
+    [-665, -3052, 60],
+    [20, -1816, 60],
+    [127, -1799, 60],
+    [263, -2572, 48],
+    [405, -2570, 108],
+    [851, -4183, 120],
+    [754, -3971, 108],
+    [1757, -5065, 132],
+    [1169, -4453, 108],
+    [2054, -5244, 108],
+    [1631, -4901, 108],
+    [2305, -5281, 108],
+    [1007, -4281, 96],
+    [2766, -5202, 96],
+    [2927, -5204, 84],
+    [3206, -5218, 36],
+    [3099, -5193, 72],
+    [1417, -4726, 120],
+    [2844, -1513, 60],
+    [3206, -1464, 120],
+    [2881, -1403, 108],
+    [3804, -2025, 84],
+    [4116, -1778, 108],
+    [3715, -1508, 72],
+    [4247, -1126, 72],
+    [3860, 268, 60],
+    [4334, -1011, 132],
+    [3849, 349, 72],
+    [3956, 490, 96],
+    [4073, 667, 108],
+    [3583, -864, 96],
+    [4135, 836, 120],
+    [4785, -743, 120],
+    [4993, -839, 108],
+    [5224, -482, 84],
+    [5235, -1238, 132],
+    [5419, -1346, 72],
+    [6075, -5099, 96],
+    [5767, -4953, 108],
+    [5896, -4967, 108],
+    [5384, -4642, 108],
+    [5704, -4857, 108],
+    [5563, -4697, 108],
+    [5406, -4470, 132],
+    [5352, -3964, 108],
+    [5309, -3665, 132],
+    [5247, -3464, 108],
+    [5300, -3121, 108],
+    [3524, -3340, 132],
+    [3661, -3589, 108],
+    [7236, -1376, 72],
+    [7624, -1610, 96],
+    [7403, -1555, 84],
+    [7514, -1568, 108],
+    [3660, -2705, 108],
+    [3374, -2813, 96],
+    [7347, -1447, 108],
+    [7236, -775, 60],
+    [7207, -631, 108],
+    [7303, -468, 108],
+    [7262, -1263, 108],
+    [7404, -350, 120],
+    [7589, -305, 108],
+    [7741, -1589, 120],
+    [7949, -1594, 120],
+    [8152, -1599, 132],
+    [8378, -1602, 144],
+    [7873, -321, 72],
+    [8543, -1661, 84],
+    [7790, -259, 120],
+    [8675, -1573, 120],
+    [8163, -245, 84],
+    [8329, -311, 84],
+    [8275, -229, 120],
+    [8447, -277, 120],
+    [8824, -1447, 108],
+    [7221, -1140, 60],
+    [8924, -1273, 108],
+    [6844, -950, 120],
+    [8949, -1060, 132],
+    [8904, -920, 96],
+    [8582, -338, 84],
+    [8963, -803, 120],
+    [8680, -322, 108],
+    [8811, -449, 108],
+    [8910, -610, 108],
+    [6855, 114, 96],
+    [6971, 241, 120],
+    [6852, 656, 60],
+    [6980, 706, 108],
+    [6946, 939, 120],
+    [6027, -560, 72],
+    [7521, 425, 48],
+    [7599, 389, 60],
+    [5863, -431, 108],
+    [9392, 262, 108],
+    [7521, 512, 72],
+    [9807, 1027, 60],
+    [9554, 237, 120],
+    [9346, 392, 144],
+    [9789, 1142, 84],
+    [9747, -532, 96],
+    [8591, 347, 72],
+    [9951, -509, 120],
+    [9308, 2417, 60],
+    [10185, -522, 108],
+    [10330, 2147, 72],
+    [9350, 2480, 84],
+    [10503, 2124, 108],
+    [12500, 2628, 60],
+    [13188, 2864, 60],
+    [12637, 2659, 84],
+    [13262, 2899, 60],
+    [13777, 5168, 60],
+    [15709, 6399, 48],
+    [13539, 5664, 60],
+    [15660, 6474, 48],
+    [13743, 5248, 84],
+    [15482, 6600, 48],
+    [15591, 6525, 36],
+    [13487, 5738, 84],
+    [15407, 6702, 72],
+    [8171, -2568, 60],
+    [16001, 6015, 72],
+    [16017, 6110, 48],
+    [6496, -1491, 60],
+    [6626, -1480, 84],
+    [6190, -1022, 96],
+    [8325, -2615, 120],
+    [8222, -2412, 120],
+    [9204, -2288, 108],
+    [9279, -2216, 120],
+    [10375, -1558, 96],
+    [10309, -1421, 108],
+    [10247, -1216, 144],
+    [10079, -2310, 108],
+    [10320, -2330, 120],
+    [10942, -2963, 108],
+    [10807, -2778, 132],
+    [12989, -1929, 72],
+    [12613, -1181, 48],
+    [12559, -1120, 60],
+    [11642, -1900, 84],
+    [11558, -1692, 108],
+    [11509, -1479, 60],
+    [12559, -2673, 96],
+    [12446, -2487, 120],
+    [12375, -2303, 108],
+    [10363, -3514, 84],
+    [10290, -3340, 108],
+    [10162, -3207, 84],
+    [9003, -3048, 108],
+    [9161, -3119, 72],
+    [14550, -3462, 108],
+    [14407, -3335, 108],
+    [14366, -4493, 108],
+    [14477, -4437, 120],
+    [15305, -4230, 108],
+    [15481, -4283, 108],
+    [15349, -5009, 48],
+    [11874, -4879, 72],
+    [15453, -4984, 84],
+    [11907, -4742, 96],
+    [12440, -4278, 60],
+    [11980, -4582, 108],
+    [12131, -4387, 132],
+    [15681, -4973, 120],
+    [12591, -4252, 96],
+    [12777, -4244, 108],
+    [12969, -4227, 108],
+    [15897, -5071, 108],
+    [13204, -4228, 120],
+    [11592, -5261, 84],
+    [12743, -4826, 48],
+    [10102, -5078, 84],
+    [12854, -4782, 108],
+    [10191, -5033, 84],
+    [10523, -5133, 120],
+    [10336, -4977, 132],
+    [10667, -5250, 96],
+    [9665, -6403, 84],
+    [10798, -5379, 60],
+    [9670, -5547, 108],
+    [9834, -6369, 120],
+    [9864, -5572, 132],
+    [11362, -3957, 108],
+    [11162, -3830, 120],
+    [8922, -6173, 120],
+    [9003, -5368, 60],
+    [8453, -6153, 72],
+    [8954, -5229, 108],
+    [8905, -5072, 96],
+    [8704, -3873, 108],
+    [8578, -6105, 96],
+    [8508, -3710, 84],
+    [8614, -5400, 60],
+    [8788, -4922, 132],
+    [8936, -3905, 72],
+    [9701, -4613, 108],
+    [6973, -4776, 72],
+    [7016, -4674, 108],
+    [9124, -3853, 108],
+    [7253, -4648, 96],
+    [6602, -4591, 72],
+    [8510, -5322, 108],
+    [6687, -3810, 84],
+    [9525, -4492, 132],
+    [7461, -4705, 108],
+    [9280, -3812, 96],
+    [6467, -2811, 36],
+    [6842, -3820, 96],
+    [6483, -2725, 72],
+    [7675, -4864, 132],
+    [6991, -2885, 108],
+    [6610, -2664, 108],
+    [6822, -2736, 108],
+    [6763, -4582, 108],
+    [6509, -2490, 108],
+    [7216, -3865, 132],
+    [7018, -3708, 120],
+    [2254, -3301, 96],
+    [6378, -2310, 96],
+    [7197, -2857, 108],
+    [2609, -3483, 60],
+    [2449, -3385, 108],
+    [4585, -2889, 84],
+    [4470, -2768, 96],
+    [4083, -4033, 108],
+    [1568, -2869, 48],
+    [4336, -4105, 144],
+    [1977, -1678, 60],
+    [1412, -3642, 60],
+    [1570, -2792, 72],
+    [1932, -1586, 72],
+    [1310, -3547, 72],
+    [3611, 1391, 120],
+    [3558, 2174, 108],
+    [3431, 1563, 108],
+    [3142, 2813, 84],
+    [3330, 2296, 132],
+    [3001, 2818, 72],
+    [3703, 2044, 48],
+    [2872, 3863, 108],
+    [3125, 2942, 132],
+    [2841, 4018, 132],
+    [2402, 5140, 72],
+    [2511, 5167, 96],
+    [-14607, -5112, 108],
+    [-14430, -5180, 108],
+    [-14197, -5222, 144],
+    [-14895, -4703, 60],
+    [-14797, -4728, 48],
+    [-14697, -4739, 48],
+    [-13919, -5281, 132],
+    [-13646, -5170, 84],
+    [-13400, -5068, 144],
+    [-13099, -5108, 132],
+    [-12824, -5092, 108],
+    [-12631, -5044, 108],
+    [-12427, -4914, 108],
+    [-12270, -4816, 84],
+    [-11772, -4983, 84],
+    [-11940, -4867, 108],
+    [-12091, -4699, 108],
+    [-12270, -4529, 84],
+    [-12460, -4396, 108],
+    [-13058, -4252, 108],
+    [-12894, -4096, 84],
+    [-12738, -4077, 84],
+    [-13546, -4341, 48],
+    [-13428, -4299, 108],
+    [-14679, -4192, 84],
+    [-14368, -4308, 84],
+    [-14495, -4133, 132],
+    [-12072, -3824, 96],
+    [-11904, -3648, 120],
+    [-11654, -3569, 84],
+    [-11648, -3357, 108],
+    [-11420, -3359, 108],
+    [-11296, -3135, 84],
+    [-10782, -2838, 96],
+    [-11410, -3039, 36],
+    [-10581, -2773, 132],
+    [-11118, -5114, 96],
+    [-10675, -5079, 108],
+    [-10205, -4890, 96],
+    [-11543, -4164, 96],
+    [-11287, -4244, 60],
+    [-10018, -4747, 120],
+    [-9278, 419, 60],
+    [-9341, 470, 60],
+    [-9180, 496, 96],
+    [-11365, -4131, 120],
+    [-9353, 0, 72],
+    [-8975, 528, 120],
+    [-9413, 89, 108],
+    [-8230, 770, 48],
+    [-9231, 17, 84],
+    [-6808, 1667, 60],
+    [-6694, 1622, 96],
+    [-8285, 855, 72],
+    [-6793, 1796, 96],
+    [-6865, 1935, 60],
+    [-7043, 2222, 72],
+    [-7083, 2368, 72],
+    [-7103, 2533, 84],
+    [-7099, 2721, 96],
+    [-7178, 2874, 48],
+    [-6964, 3110, 84],
+    [-6246, 4191, 72],
+    [-6735, 3446, 48],
+    [-6722, 3537, 84],
+    [-6396, 3388, 108],
+    [-6787, 3140, 108],
+    [-6227, 4488, 84],
+    [-6281, 3953, 72],
+    [-6530, 3576, 132],
+    [-6147, 3994, 132],
+    [-6141, 4292, 144],
+    [-6377, 5578, 84],
+    [-6152, 4569, 108],
+    [-6222, 4769, 84],
+    [-6266, 4956, 72],
+    [-6233, 5414, 108],
+    [-6347, 5646, 84],
+    [-6400, 6066, 84],
+    [-6411, 6261, 96],
+    [-6565, 7043, 72],
+    [-6377, 6415, 108],
+    [-6440, 6614, 84],
+    [-5944, 1954, 60],
+    [-6001, 2039, 96],
+    [-6467, 7070, 108],
+    [-4963, 3608, 72],
+    [-4776, 3510, 48],
+    [-5785, 1998, 132],
+    [-3708, 3449, 72],
+    [-3790, 3582, 108],
+    [-4031, 3697, 84],
+    [-4859, 3634, 120],
+    [-3850, 3730, 120],
+    [-4689, 4605, 108],
+    [-3492, 2892, 72],
+    [-4507, 4483, 72],
+    [-4829, 2745, 108],
+    [-4435, 4273, 108],
+    [-3383, 2930, 108],
+    [-4888, 2821, 108],
+    [-5372, 5172, 60],
+    [-5036, 2981, 108],
+    [-9895, -1942, 120],
+    [-9371, -2159, 72],
+    [-9995, -1773, 108],
+    [-5280, 5218, 108],
+    [-5079, 5183, 108],
+    [-5149, 3079, 72],
+    [-9695, -2048, 120],
+    [-5063, 5374, 108],
+    [-9504, -2059, 120],
+    [-8948, -2140, 84],
+    [-9262, -2081, 132],
+    [-9068, -2071, 120],
+    [-8861, -2045, 132],
+    [-8654, -2083, 120],
+    [-8473, -2048, 120],
+    [-8283, -1965, 120],
+    [-8182, -1863, 108],
+    [-10037, -1579, 108],
+    [-10063, -1365, 108],
+    [-10074, -1190, 108],
+    [-8167, -1225, 60],
+    [-8188, -1087, 96],
+    [-9975, -1022, 108],
+    [-9835, -910, 120],
+    [-9709, -848, 120],
+    [-9532, -792, 120],
+    [-9348, -779, 120],
+    [-9131, -784, 132],
+    [-8754, -795, 84],
+    [-8587, -797, 120],
+    [-8424, -856, 108],
+    [-8246, -921, 108],
+    [-8153, -1701, 84],
+    [-6447, -2137, 72],
+    [-8153, -1591, 60],
+    [-7694, -1393, 120],
+    [-6541, -2030, 84],
+    [-7135, -1547, 84],
+    [-5885, -2958, 72],
+    [-7171, -1440, 96],
+    [-5620, -3123, 72],
+    [-6631, -1866, 96],
+    [-5882, -3696, 96],
+    [-5716, -2944, 108],
+    [-5949, -3528, 60],
+    [-6869, -4052, 96],
+    [-7081, -2673, 60],
+    [-6725, -4069, 72],
+    [-10487, -2295, 60],
+    [-7032, -2749, 72],
+    [-10769, -3817, 72],
+    [-6607, -4081, 60],
+    [-10849, -1824, 84],
+    [-10479, -2146, 108],
+    [-7003, -2611, 108],
+    [-10749, -1511, 72],
+    [-10697, -1953, 120],
+    [-10848, -1330, 108],
+    [-10912, -1220, 60],
+    [-9843, -2685, 84],
+    [-10823, -3717, 84],
+    [-9697, -2713, 72],
+    [-10080, -3431, 48],
+    [-10648, -3844, 108],
+    [-9530, -2695, 120],
+    [-2387, -6791, 108],
+    [-8465, -5037, 84],
+    [-2550, -6627, 108],
+    [-9325, -2822, 84],
+    [-10140, -3346, 96],
+    [-8461, -4908, 108],
+    [8116, -1076, 96],
+    [8115, -940, 96],
+    [-2503, -6359, 168],
+    [-9062, -1580, 96],
+    [8120, -790, 96],
+    [-9065, -1444, 96],
+    [-2615, -6159, 120],
+    [-2744, -5943, 132],
+    [-3713, -4955, 108],
+    [-9061, -1292, 96],
+    [-1551, -4718, 60],
+    [5251, -6249, 60],
+    [5192, -6181, 48],
+    [-2898, -5732, 108],
+    [-1705, -4629, 96],
+    [1618, -7035, 60],
+    [-3829, -4776, 108],
+    [-2851, -5589, 132],
+    [-2974, -5399, 132],
+    [-3952, -4568, 108],
+    [-4135, -4368, 132],
+    [-3428, -5104, 132],
+    [-3167, -5254, 108],
+    [-3049, -6916, 120],
+    [-3097, -6722, 108],
+    [-3190, -6493, 120],
+    [-3318, -6284, 132],
+    [-4672, -5861, 132],
+    [-4480, -5668, 120],
+    [-4294, -5548, 108],
+    [-3433, -6072, 120],
+    [-3568, -5881, 120],
+    [-3736, -5698, 144],
+    [-3978, -7517, 108],
+    [-3956, -5572, 120],
+    [-4103, -5400, 120],
+    [-4281, -5240, 120],
+    [-4409, -5034, 120],
+    [-4475, -4808, 108],
+    [-2777, -7546, 120],
+    [-2542, -7502, 108],
+    [-2398, -7364, 108],
+    [-2350, -7151, 108],
+    [-2315, -6976, 96],
+    [-3749, -7488, 120],
+    [-2995, -7596, 144],
+    [-3253, -7558, 132],
+    [-3468, -7472, 120],
+    [-5028, -6473, 120],
+    [-3604, -7301, 132],
+    [-3678, -7078, 132],
+    [-3835, -6826, 132],
+    [-3935, -6595, 120],
+    [-5214, -7381, 120],
+    [-4065, -6416, 132],
+    [-4174, -6231, 84],
+    [-4556, -6940, 120],
+    [-4663, -6781, 120],
+    [-4748, -6548, 156],
+    [-4847, -6257, 132],
+    [-4806, -6016, 108],
+    [-4950, -7410, 168],
+    [-5470, -7251, 156],
+    [-5637, -7038, 120],
+    [-5593, -6819, 120],
+    [-5406, -6727, 120],
+    [-5263, -6580, 120],
+    [-4636, -7476, 120],
+    [-4399, -7496, 108],
+    [-6769, -7571, 108],
+    [-6853, -7377, 108],
+    [-8273, -7231, 60],
+    [-4182, -7512, 108],
+    [-7525, -5855, 108],
+    [-7495, -6329, 60],
+    [-7297, -6946, 108],
+    [-8148, -7137, 84],
+    [-7030, -7301, 84],
+];
@@ -0,0 +1,276 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f32::consts::PI;
+use std::sync::Mutex;
+
+use airmash_protocol::Position;
+
+use super::terrain::Terrain;
+
+/// Number of vertices placed around each hill.
+const SIDES: usize = 8;
+/// Edges between nodes longer than this aren't considered.
+/// This keeps the number of visibility checks down. It
+/// doesn't apply to the start and goal, which can be far
+/// out in open water away from any hill.
+const MAX_EDGE: f32 = 3000.0;
+
+const BOUND_X: f32 = 16384.0;
+const BOUND_Y: f32 = 8192.0;
+
+/// A graph of the points from which planes can fly
+/// around hills, along with which pairs of points can
+/// see each other.
+///
+/// Each hill is grown by the clearance and a polygon
+/// is placed around it whose edges are tangent to the
+/// grown circle. The corners of these polygons are the
+/// nodes of the graph. Edges between nodes are only
+/// worked out the first time that a search needs them.
+#[derive(Debug)]
+pub struct VisibilityGraph {
+    terrain: Terrain,
+    nodes: Vec<(f32, f32)>,
+    edges: Mutex<Vec<Option<Vec<(usize, f32)>>>>,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+struct Entry {
+    estimate: f32,
+    cost: f32,
+    node: usize,
+}
+
+impl Eq for Entry {}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so that BinaryHeap pops the cheapest entry
+        other
+            .estimate
+            .partial_cmp(&self.estimate)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn dist(a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (a.0 - b.0, a.1 - b.1);
+
+    (dx * dx + dy * dy).sqrt()
+}
+
+fn to_point(pos: Position) -> (f32, f32) {
+    (pos.x.inner(), pos.y.inner())
+}
+
+impl VisibilityGraph {
+    /// Build the graph for the given terrain, keeping
+    /// paths at least `clearance` away from every hill.
+    pub fn new(terrain: &Terrain, clearance: f32) -> Self {
+        let terrain = terrain.inflated(clearance);
+        let mut nodes = vec![];
+
+        // Place the corners far enough out that the edges
+        // of the polygon just touch the circle.
+        let scale = 1.0 / (PI / SIDES as f32).cos();
+
+        for c in terrain.circles() {
+            for i in 0..SIDES {
+                let angle = i as f32 * 2.0 * PI / SIDES as f32;
+                let r = c.radius * scale;
+                let (x, y) = (c.x + r * angle.cos(), c.y + r * angle.sin());
+
+                if x.abs() > BOUND_X - clearance || y.abs() > BOUND_Y - clearance {
+                    continue;
+                }
+
+                // Corners inside another hill are useless
+                let blocked = terrain
+                    .nearby((x, y), (x, y))
+                    .any(|other| other.contains(x, y));
+                if !blocked {
+                    nodes.push((x, y));
+                }
+            }
+        }
+
+        let edges = Mutex::new(vec![None; nodes.len()]);
+
+        Self {
+            terrain,
+            nodes,
+            edges,
+        }
+    }
+
+    /// The terrain with every hill grown by the clearance
+    /// that the graph was built with.
+    pub fn terrain(&self) -> &Terrain {
+        &self.terrain
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = Position> + '_ {
+        self.nodes.iter().map(|&(x, y)| Position::new(x, y))
+    }
+
+    /// The nodes directly visible from the given node,
+    /// along with the distance to each.
+    pub fn edges(&self, node: usize) -> Vec<(usize, f32)> {
+        if let Some(ref edges) = self.edges.lock().unwrap()[node] {
+            return edges.clone();
+        }
+
+        let edges = self.visible_from(self.nodes[node], MAX_EDGE);
+        self.edges.lock().unwrap()[node] = Some(edges.clone());
+
+        edges
+    }
+
+    /// The nodes within `max_dist` that can be seen from
+    /// `pos`, along with the distance to each.
+    fn visible_from(&self, pos: (f32, f32), max_dist: f32) -> Vec<(usize, f32)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &other)| {
+                let d = dist(pos, other);
+
+                if d == 0.0 || d > max_dist || !self.terrain.segment_clear(pos, other) {
+                    return None;
+                }
+
+                Some((i, d))
+            })
+            .collect()
+    }
+
+    /// Find a short path between two points that keeps
+    /// clear of the terrain.
+    ///
+    /// The returned path doesn't include `start` and
+    /// always ends at `goal`. Returns `None` if `goal`
+    /// is too close to a hill or cannot be reached.
+    pub fn find_path(&self, start: Position, goal: Position) -> Option<Vec<Position>> {
+        if self.terrain.is_blocked(goal) {
+            return None;
+        }
+        if self.terrain.line_of_sight(start, goal) {
+            return Some(vec![goal]);
+        }
+
+        let start_pt = to_point(start);
+        let goal_pt = to_point(goal);
+        // The start and goal get the two indices after
+        // the real nodes.
+        let start_node = self.nodes.len();
+        let goal_node = start_node + 1;
+
+        let mut cost = vec![std::f32::INFINITY; self.nodes.len() + 2];
+        let mut parent = vec![usize::max_value(); self.nodes.len() + 2];
+        let mut open = BinaryHeap::new();
+
+        cost[start_node] = 0.0;
+        open.push(Entry {
+            estimate: dist(start_pt, goal_pt),
+            cost: 0.0,
+            node: start_node,
+        });
+
+        while let Some(entry) = open.pop() {
+            if entry.node == goal_node {
+                break;
+            }
+            if entry.cost > cost[entry.node] {
+                continue;
+            }
+
+            let pos = match entry.node {
+                n if n == start_node => start_pt,
+                n => self.nodes[n],
+            };
+            let mut edges = match entry.node {
+                n if n == start_node => self.visible_from(start_pt, std::f32::INFINITY),
+                n => self.edges(n),
+            };
+
+            let to_goal = dist(pos, goal_pt);
+            if self.terrain.segment_clear(pos, goal_pt) {
+                edges.push((goal_node, to_goal));
+            }
+
+            for (next, d) in edges {
+                let next_cost = entry.cost + d;
+
+                if next_cost < cost[next] {
+                    cost[next] = next_cost;
+                    parent[next] = entry.node;
+
+                    let remaining = match next {
+                        n if n == goal_node => 0.0,
+                        n => dist(self.nodes[n], goal_pt),
+                    };
+                    open.push(Entry {
+                        estimate: next_cost + remaining,
+                        cost: next_cost,
+                        node: next,
+                    });
+                }
+            }
+        }
+
+        if parent[goal_node] == usize::max_value() {
+            return None;
+        }
+
+        let mut path = vec![goal];
+        let mut node = parent[goal_node];
+        while node != start_node {
+            let (x, y) = self.nodes[node];
+            path.push(Position::new(x, y));
+            node = parent[node];
+        }
+        path.reverse();
+
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{DEFAULT_CLEARANCE, GRAPH};
+
+    #[test]
+    fn path_between_distant_open_water() {
+        // Both of these are well over MAX_EDGE away from
+        // the nearest node and can't see each other.
+        let start = Position::new(-16000.0, 8000.0);
+        let goal = Position::new(16000.0, -500.0);
+        assert!(!GRAPH.terrain().line_of_sight(start, goal));
+
+        let path = GRAPH.find_path(start, goal).expect("No path found");
+        assert_eq!(to_point(*path.last().unwrap()), to_point(goal));
+
+        let mut prev = start;
+        for &pos in path.iter() {
+            assert!(GRAPH.terrain().line_of_sight(prev, pos));
+            prev = pos;
+        }
+    }
+
+    #[test]
+    fn path_is_direct_with_line_of_sight() {
+        let graph = VisibilityGraph::new(&Terrain::new(vec![]), DEFAULT_CLEARANCE);
+        let goal = Position::new(1000.0, 1000.0);
+
+        let path = graph.find_path(Position::new(0.0, 0.0), goal).unwrap();
+        assert_eq!(path.len(), 1);
+        assert_eq!(to_point(path[0]), to_point(goal));
+    }
+}
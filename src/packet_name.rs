@@ -0,0 +1,76 @@
+//! Human readable names for packets, for logging and
+//! for collecting statistics by packet type.
+
+use crate::protocol::{ClientPacket, ServerPacket};
+
+/// The name of a server packet's type, e.g. `"PlayerUpdate"`.
+pub fn server_packet_name(packet: &ServerPacket) -> &'static str {
+    match packet {
+        ServerPacket::Login(_) => "Login",
+        ServerPacket::Backup => "Backup",
+        ServerPacket::Ping(_) => "Ping",
+        ServerPacket::PingResult(_) => "PingResult",
+        ServerPacket::Ack => "Ack",
+        ServerPacket::Error(_) => "Error",
+        ServerPacket::CommandReply(_) => "CommandReply",
+        ServerPacket::PlayerNew(_) => "PlayerNew",
+        ServerPacket::PlayerLeave(_) => "PlayerLeave",
+        ServerPacket::PlayerUpdate(_) => "PlayerUpdate",
+        ServerPacket::PlayerFire(_) => "PlayerFire",
+        ServerPacket::PlayerRespawn(_) => "PlayerRespawn",
+        ServerPacket::PlayerFlag(_) => "PlayerFlag",
+        ServerPacket::PlayerHit(_) => "PlayerHit",
+        ServerPacket::PlayerKill(_) => "PlayerKill",
+        ServerPacket::PlayerUpgrade(_) => "PlayerUpgrade",
+        ServerPacket::PlayerType(_) => "PlayerType",
+        ServerPacket::PlayerPowerup(_) => "PlayerPowerup",
+        ServerPacket::PlayerLevel(_) => "PlayerLevel",
+        ServerPacket::PlayerReteam(_) => "PlayerReteam",
+        ServerPacket::GameFlag(_) => "GameFlag",
+        ServerPacket::GameSpectate(_) => "GameSpectate",
+        ServerPacket::GamePlayersAlive(_) => "GamePlayersAlive",
+        ServerPacket::GameFirewall(_) => "GameFirewall",
+        ServerPacket::EventRepel(_) => "EventRepel",
+        ServerPacket::EventBoost(_) => "EventBoost",
+        ServerPacket::EventBounce(_) => "EventBounce",
+        ServerPacket::EventStealth(_) => "EventStealth",
+        ServerPacket::EventLeaveHorizon(_) => "EventLeaveHorizon",
+        ServerPacket::MobUpdate(_) => "MobUpdate",
+        ServerPacket::MobUpdateStationary(_) => "MobUpdateStationary",
+        ServerPacket::MobDespawn(_) => "MobDespawn",
+        ServerPacket::MobDespawnCoords(_) => "MobDespawnCoords",
+        ServerPacket::ScoreUpdate(_) => "ScoreUpdate",
+        ServerPacket::ScoreBoard(_) => "ScoreBoard",
+        ServerPacket::ScoreDetailedFFA(_) => "ScoreDetailedFFA",
+        ServerPacket::ScoreDetailedCTF(_) => "ScoreDetailedCTF",
+        ServerPacket::ScoreDetailedBTR(_) => "ScoreDetailedBTR",
+        ServerPacket::ChatTeam(_) => "ChatTeam",
+        ServerPacket::ChatPublic(_) => "ChatPublic",
+        ServerPacket::ChatSay(_) => "ChatSay",
+        ServerPacket::ChatWhisper(_) => "ChatWhisper",
+        ServerPacket::ChatVoteMutePassed(_) => "ChatVoteMutePassed",
+        ServerPacket::ChatVoteMuted => "ChatVoteMuted",
+        ServerPacket::ServerMessage(_) => "ServerMessage",
+        ServerPacket::ServerCustom(_) => "ServerCustom",
+    }
+}
+
+/// The name of a client packet's type, e.g. `"Key"`.
+pub fn client_packet_name(packet: &ClientPacket) -> &'static str {
+    match packet {
+        ClientPacket::Login(_) => "Login",
+        ClientPacket::Backup(_) => "Backup",
+        ClientPacket::Horizon(_) => "Horizon",
+        ClientPacket::Ack => "Ack",
+        ClientPacket::Pong(_) => "Pong",
+        ClientPacket::Key(_) => "Key",
+        ClientPacket::Command(_) => "Command",
+        ClientPacket::ScoreDetailed => "ScoreDetailed",
+        ClientPacket::Chat(_) => "Chat",
+        ClientPacket::TeamChat(_) => "TeamChat",
+        ClientPacket::Whisper(_) => "Whisper",
+        ClientPacket::Say(_) => "Say",
+        ClientPacket::VoteMute(_) => "VoteMute",
+        ClientPacket::LocalPing(_) => "LocalPing",
+    }
+}
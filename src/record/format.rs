@@ -0,0 +1,235 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const MAGIC: &[u8; 5] = b"AMREC";
+const VERSION: u8 = 1;
+
+/// Which way a websocket frame was travelling.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Sent by the server to the client.
+    Inbound,
+    /// Sent by the client to the server.
+    Outbound,
+}
+
+/// A single recorded websocket frame.
+#[derive(Clone, Debug)]
+pub struct Record {
+    /// Time since the recording started.
+    pub time: Duration,
+    pub direction: Direction,
+    /// The raw frame as it appears on the wire.
+    pub data: Vec<u8>,
+}
+
+/// Something that can observe the websocket frames
+/// going through a [`ClientBase`].
+///
+/// [`ClientBase`]: crate::ClientBase
+pub trait FrameSink: Send {
+    fn frame(&mut self, direction: Direction, data: &[u8]) -> io::Result<()>;
+}
+
+/// Writes frames to a compact binary capture file.
+///
+/// The file consists of a short header followed by
+/// one entry per frame containing the direction,
+/// a timestamp in microseconds and the frame bytes.
+/// It can be read back with [`RecordReader`].
+pub struct Recorder {
+    out: BufWriter<Box<dyn Write + Send>>,
+    start: Instant,
+}
+
+impl Recorder {
+    /// Create a new capture file at `path`, replacing
+    /// it if it already exists.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::new(File::create(path)?)
+    }
+
+    pub fn new<W>(out: W) -> io::Result<Self>
+    where
+        W: Write + Send + 'static,
+    {
+        let mut out = BufWriter::new(Box::new(out) as Box<dyn Write + Send>);
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION])?;
+
+        Ok(Self {
+            out,
+            start: Instant::now(),
+        })
+    }
+
+    /// Write a frame with an explicit timestamp.
+    pub fn write(&mut self, time: Duration, direction: Direction, data: &[u8]) -> io::Result<()> {
+        let dir = match direction {
+            Direction::Inbound => 0u8,
+            Direction::Outbound => 1u8,
+        };
+        let micros = time.as_secs() * 1_000_000 + time.subsec_micros() as u64;
+
+        self.out.write_all(&[dir])?;
+        self.out.write_all(&micros.to_le_bytes())?;
+        self.out.write_all(&(data.len() as u32).to_le_bytes())?;
+        self.out.write_all(data)?;
+
+        // Captures are most useful after something went
+        // wrong, so don't leave frames sitting in the buffer.
+        self.out.flush()
+    }
+}
+
+impl FrameSink for Recorder {
+    fn frame(&mut self, direction: Direction, data: &[u8]) -> io::Result<()> {
+        let time = Instant::now() - self.start;
+        self.write(time, direction, data)
+    }
+}
+
+/// Reads the frames from a capture written by a
+/// [`Recorder`].
+pub struct RecordReader<R> {
+    inner: R,
+}
+
+impl RecordReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> RecordReader<R> {
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut header = [0u8; 6];
+        inner.read_exact(&mut header)?;
+
+        if &header[..5] != MAGIC {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "File is not a packet capture",
+            ));
+        }
+        if header[5] != VERSION {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Unsupported capture version {}", header[5]),
+            ));
+        }
+
+        Ok(Self { inner })
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<Record>> {
+        let mut dir = [0u8; 1];
+        match self.inner.read_exact(&mut dir) {
+            Ok(()) => (),
+            Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let direction = match dir[0] {
+            0 => Direction::Inbound,
+            1 => Direction::Outbound,
+            x => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid frame direction {}", x),
+                ));
+            }
+        };
+
+        let mut micros = [0u8; 8];
+        let mut len = [0u8; 4];
+        self.inner.read_exact(&mut micros)?;
+        self.inner.read_exact(&mut len)?;
+
+        let mut data = vec![0u8; u32::from_le_bytes(len) as usize];
+        self.inner.read_exact(&mut data)?;
+
+        let micros = u64::from_le_bytes(micros);
+
+        Ok(Some(Record {
+            time: Duration::from_micros(micros),
+            direction,
+            data,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for RecordReader<R> {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<io::Result<Record>> {
+        match self.read_record() {
+            Ok(Some(record)) => Some(Ok(record)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// A writer that can still be read after the
+    /// recorder that owns it has been dropped.
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = SharedBuf::default();
+        let mut recorder = Recorder::new(buf.clone()).unwrap();
+        recorder
+            .write(Duration::from_micros(1500), Direction::Outbound, &[1, 2, 3])
+            .unwrap();
+        recorder
+            .write(Duration::from_secs(2), Direction::Inbound, &[])
+            .unwrap();
+        drop(recorder);
+
+        let data = buf.0.lock().unwrap().clone();
+        let records = RecordReader::new(&data[..])
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].time, Duration::from_micros(1500));
+        assert_eq!(records[0].direction, Direction::Outbound);
+        assert_eq!(records[0].data, vec![1, 2, 3]);
+        assert_eq!(records[1].time, Duration::from_secs(2));
+        assert_eq!(records[1].direction, Direction::Inbound);
+        assert!(records[1].data.is_empty());
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(RecordReader::new(&b"NOTREC"[..]).is_err());
+        assert!(RecordReader::new(&b"AMREC\x02"[..]).is_err());
+    }
+
+    #[test]
+    fn truncated_frame_is_an_error() {
+        let data = b"AMREC\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x05\x00\x00\x00\x01";
+        let mut reader = RecordReader::new(&data[..]).unwrap();
+
+        assert!(reader.next().unwrap().is_err());
+    }
+}
@@ -0,0 +1,7 @@
+//! Capturing websocket traffic and replaying it later.
+
+mod format;
+mod replay;
+
+pub use self::format::{Direction, FrameSink, Record, RecordReader, Recorder};
+pub use self::replay::{ReplayClient, ReplaySpeed};
@@ -0,0 +1,247 @@
+use std::collections::VecDeque;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use tokio::r#await;
+use tokio::timer::Delay;
+
+use airmash_protocol::*;
+use airmash_protocol_v5::ProtocolV5;
+
+use super::{Direction, Record, RecordReader};
+use crate::game::World;
+use crate::ClientEvent;
+use crate::{Client, ClientError, ClientFuture, ClientResult, ImplClient};
+
+static TICKER_TIME: Duration = Duration::from_millis(16);
+
+/// How fast a [`ReplayClient`] plays back a capture.
+#[derive(Copy, Clone, Debug)]
+pub enum ReplaySpeed {
+    /// Play back at the speed it was recorded at.
+    RealTime,
+    /// Play back faster (or slower) than real time
+    /// by the given factor. This must be positive.
+    Scaled(f32),
+    /// Don't wait at all between events.
+    Unlimited,
+}
+
+/// A client that plays back the server packets from
+/// a capture written by a [`Recorder`].
+///
+/// `Frame` events are synthesised every 16ms of
+/// recorded time. The instants in those events are
+/// based on the recorded timestamps so that helpers
+/// such as `wait` see the recorded passage of time,
+/// even when playing back faster than real time.
+/// Packets sent by the bot are discarded.
+///
+/// [`Recorder`]: crate::Recorder
+pub struct ReplayClient {
+    pub world: World,
+    records: VecDeque<Record>,
+    pending: VecDeque<ClientEvent>,
+    speed: ReplaySpeed,
+
+    /// When playback actually started, which is also the
+    /// instant corresponding to the start of the capture.
+    /// This is `None` until the first event is requested
+    /// so that any setup done before then isn't skipped.
+    started: Option<Instant>,
+    next_frame: Duration,
+}
+
+impl Client for ReplayClient {
+    fn world(&self) -> &World {
+        &self.world
+    }
+    fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    fn _next<'a>(&'a mut self) -> ClientFuture<'a, Option<ClientEvent>> {
+        Box::new(self._next_impl())
+    }
+
+    fn _send_buf<'a>(&'a mut self, buf: Vec<u8>) -> ClientFuture<'a, ()> {
+        Box::new(self._send_buf_impl(buf))
+    }
+}
+
+/// async trait backing functions
+impl ReplayClient {
+    async fn _next_impl(&mut self) -> Result<Option<ClientEvent>, ClientError> {
+        use self::ClientEvent::*;
+
+        if let Some(evt) = self.pending.pop_front() {
+            return Ok(Some(evt));
+        }
+
+        let started = *self.started.get_or_insert_with(Instant::now);
+
+        let (time, evt) = loop {
+            match self.records.front() {
+                Some(record) if record.time < self.next_frame => {
+                    let record = self.records.pop_front().unwrap();
+
+                    match ProtocolV5 {}.deserialize_server(&record.data) {
+                        Ok(packet) => break (record.time, Packet(packet)),
+                        // Captures contain the raw frames, including
+                        // any that the server sent garbled.
+                        Err(e) => warn!(
+                            "Skipping frame at {:?} that failed to decode: {}",
+                            record.time, e
+                        ),
+                    }
+                }
+                Some(_) => {
+                    let time = self.next_frame;
+                    self.next_frame += TICKER_TIME;
+
+                    break (time, Frame(started + time));
+                }
+                None => return Ok(None),
+            }
+        };
+
+        r#await!(wait_for(started, time, self.speed))?;
+
+        match &evt {
+            Packet(p) => {
+                let events = self.world.handle_packet(p);
+                self.pending.extend(events.into_iter().map(Game));
+            }
+            Frame(now) => self.world.update(*now),
+            Disconnected(_) | Reconnected | Game(_) => (),
+        }
+
+        Ok(Some(evt))
+    }
+
+    async fn _send_buf_impl(&mut self, _: Vec<u8>) -> Result<(), ClientError> {
+        Ok(())
+    }
+}
+
+/// Constructors
+impl ReplayClient {
+    /// Load a capture from a file.
+    pub fn open<P: AsRef<Path>>(path: P, speed: ReplaySpeed) -> io::Result<Self> {
+        let records = RecordReader::open(path)?.collect::<Result<Vec<_>, _>>()?;
+
+        Self::from_records(records, speed)
+    }
+
+    /// Create a client that plays back the given records.
+    /// Only inbound records are used.
+    ///
+    /// Fails if `speed` is scaled by a factor that isn't
+    /// positive.
+    pub fn from_records<I>(records: I, speed: ReplaySpeed) -> io::Result<Self>
+    where
+        I: IntoIterator<Item = Record>,
+    {
+        if let ReplaySpeed::Scaled(factor) = speed {
+            if factor.is_nan() || factor <= 0.0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid replay speed factor {}", factor),
+                ));
+            }
+        }
+
+        Ok(Self {
+            world: World::default(),
+            records: records
+                .into_iter()
+                .filter(|r| r.direction == Direction::Inbound)
+                .collect(),
+            pending: VecDeque::new(),
+            speed,
+            started: None,
+            next_frame: Duration::from_secs(0),
+        })
+    }
+
+    /// The number of packets that have yet to be played back.
+    pub fn remaining(&self) -> usize {
+        self.records.len()
+    }
+}
+
+impl Deref for ReplayClient {
+    type Target = ImplClient<Self>;
+
+    fn deref(&self) -> &ImplClient<Self> {
+        unsafe { std::mem::transmute(self) }
+    }
+}
+
+impl DerefMut for ReplayClient {
+    fn deref_mut(&mut self) -> &mut ImplClient<Self> {
+        unsafe { std::mem::transmute(self) }
+    }
+}
+
+/// Wait until the given capture time should be played
+/// back, with playback having started at `started`.
+async fn wait_for(started: Instant, time: Duration, speed: ReplaySpeed) -> ClientResult<()> {
+    let real = match speed {
+        ReplaySpeed::RealTime => time,
+        ReplaySpeed::Scaled(factor) => scale_duration(time, 1.0 / factor),
+        ReplaySpeed::Unlimited => return Ok(()),
+    };
+
+    let target = started + real;
+    if target > Instant::now() {
+        r#await!(Delay::new(target))?;
+    }
+
+    Ok(())
+}
+
+fn scale_duration(dur: Duration, factor: f32) -> Duration {
+    let nanos = (dur.as_secs() as f64 * 1.0e9 + dur.subsec_nanos() as f64) * factor as f64;
+
+    Duration::from_nanos(nanos.max(0.0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::future::block_on;
+
+    #[test]
+    fn clock_starts_with_first_event() {
+        let records = vec![Record {
+            time: Duration::from_millis(100),
+            direction: Direction::Inbound,
+            data: vec![],
+        }];
+        let client = ReplayClient::from_records(records, ReplaySpeed::RealTime).unwrap();
+
+        // Time spent setting up the bot shouldn't be
+        // skipped over.
+        std::thread::sleep(Duration::from_millis(100));
+        let before = Instant::now();
+
+        let (first, second) = block_on(async move {
+            let mut client = client;
+            let first = r#await!(client.next()).unwrap();
+            let second = r#await!(client.next()).unwrap();
+            (first, second)
+        });
+
+        match (first, second) {
+            (Some(ClientEvent::Frame(first)), Some(ClientEvent::Frame(second))) => {
+                assert!(first >= before);
+                assert_eq!(second - first, TICKER_TIME);
+                assert!(Instant::now() >= before + TICKER_TIME);
+            }
+            _ => panic!("Expected two frames"),
+        }
+    }
+}
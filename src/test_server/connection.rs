@@ -0,0 +1,114 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::sync::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use tokio::net::TcpStream;
+use tokio::prelude::*;
+use tokio::r#await;
+use tokio::timer::Interval;
+use tokio_tungstenite::{accept_async, WebSocketStream};
+use tungstenite::Message;
+
+use airmash_protocol::*;
+use airmash_protocol_v5::ProtocolV5;
+
+use super::state::ServerState;
+use crate::{ClientError, ClientResult};
+
+static TICKER_TIME: Duration = Duration::from_millis(16);
+
+type ServerSink = futures::stream::SplitSink<WebSocketStream<TcpStream>>;
+
+/// Run the physics and scheduled packets for all players.
+pub(crate) async fn run_ticker(state: Arc<Mutex<ServerState>>) {
+    let mut interval = Interval::new(Instant::now(), TICKER_TIME);
+
+    while let Some(Ok(now)) = r#await!(interval.next()) {
+        state.lock().unwrap().update(now);
+    }
+}
+
+pub(crate) async fn handle_connection(stream: TcpStream, state: Arc<Mutex<ServerState>>) {
+    let mut id = None;
+
+    if let Err(e) = r#await!(read_packets(stream, &state, &mut id)) {
+        warn!("Connection closed with an error: {}", e);
+    }
+
+    if let Some(id) = id {
+        state.lock().unwrap().logout(id);
+    }
+}
+
+async fn read_packets<'a>(
+    stream: TcpStream,
+    state: &'a Mutex<ServerState>,
+    id: &'a mut Option<u16>,
+) -> ClientResult<()> {
+    let ws_stream = r#await!(accept_async(stream))?;
+    let (sink, mut stream) = ws_stream.split();
+    let (tx, rx) = unbounded();
+
+    tokio::spawn_async(write_packets(sink, rx));
+
+    while let Some(msg) = r#await!(stream.next()) {
+        let buf = match msg? {
+            Message::Binary(buf) => buf,
+            Message::Ping(_) | Message::Pong(_) => continue,
+            Message::Text(txt) => {
+                return Err(ClientError::InvalidWsFrame(format!(
+                    "Client sent a text frame with body: {:?}",
+                    txt
+                )));
+            }
+        };
+
+        let packet = ProtocolV5 {}.deserialize_client(&buf)?;
+
+        handle_packet(state, id, &tx, packet);
+    }
+
+    Ok(())
+}
+
+fn handle_packet(
+    state: &Mutex<ServerState>,
+    id: &mut Option<u16>,
+    tx: &UnboundedSender<ServerPacket>,
+    packet: ClientPacket,
+) {
+    let mut state = state.lock().unwrap();
+
+    match (*id, packet) {
+        (None, ClientPacket::Login(login)) => *id = Some(state.login(login, tx.clone())),
+        (Some(id), packet) => state.handle_packet(id, packet),
+        // Packets sent before logging in are ignored,
+        // the same as the real server.
+        (None, _) => (),
+    }
+}
+
+async fn write_packets(mut sink: ServerSink, mut rx: UnboundedReceiver<ServerPacket>) {
+    while let Some(Ok(packet)) = r#await!(rx.next()) {
+        let bufs: Vec<_> = match ProtocolV5 {}.serialize_server(&packet) {
+            Ok(bufs) => bufs.collect(),
+            Err(e) => {
+                warn!("Failed to serialize {:?}: {}", packet, e);
+                continue;
+            }
+        };
+
+        for buf in bufs {
+            sink = match r#await!(sink.send(Message::Binary(buf))) {
+                Ok(sink) => sink,
+                // The read half will notice that the
+                // connection has closed.
+                Err(_) => return,
+            };
+        }
+    }
+
+    // The player was removed (e.g. kicked) so close the
+    // connection on them.
+    let _ = r#await!(future::poll_fn(|| sink.close()));
+}
@@ -0,0 +1,184 @@
+//! A small stand-in for an AIRMASH server.
+//!
+//! This speaks enough of the protocol for bots to log
+//! in, see each other move and react to missiles. It
+//! is meant for running bots end to end on a machine
+//! without network access, not as a real game server.
+//! Physics is done using the same dead-reckoning code
+//! that the client uses to predict positions.
+//!
+//! This is only built with the `test-server` feature.
+
+mod connection;
+mod state;
+
+pub use self::state::ServerConfig;
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use tokio::net::TcpListener;
+use tokio::prelude::*;
+use tokio::r#await;
+
+use self::state::ServerState;
+use crate::ClientResult;
+
+/// Listen for connections on `addr` and serve them
+/// until the listener fails.
+///
+/// This must be run within a tokio runtime.
+pub async fn serve(addr: SocketAddr, config: ServerConfig) -> ClientResult<()> {
+    let listener = TcpListener::bind(&addr)?;
+
+    r#await!(serve_on(listener, config))
+}
+
+/// Serve connections from a listener that has already
+/// been bound. This is useful when binding to port 0,
+/// since the real address can be read from the listener
+/// before handing it over.
+///
+/// This must be run within a tokio runtime.
+pub async fn serve_on(listener: TcpListener, config: ServerConfig) -> ClientResult<()> {
+    let state = Arc::new(Mutex::new(ServerState::new(config)));
+
+    info!("Test server listening on {}", listener.local_addr()?);
+
+    tokio::spawn_async(connection::run_ticker(state.clone()));
+
+    let mut incoming = listener.incoming();
+    while let Some(stream) = r#await!(incoming.next()) {
+        let stream = stream?;
+
+        tokio::spawn_async(connection::handle_connection(stream, state.clone()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use airmash_protocol::*;
+    use url::Url;
+
+    use crate::{ClientBase, ClientEvent, ReconnectPolicy};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Start a server on a free port and return its address.
+    fn start_server() -> SocketAddr {
+        let listener = TcpListener::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let config = ServerConfig {
+            missile_interval: None,
+            ..Default::default()
+        };
+
+        tokio::spawn_async(async move {
+            r#await!(serve_on(listener, config)).expect("The test server failed");
+        });
+
+        addr
+    }
+
+    /// Connect a client to the server at `addr` and log in.
+    async fn connect(addr: SocketAddr) -> ClientResult<ClientBase> {
+        let url = Url::parse(&format!("ws://{}", addr)).unwrap();
+        let mut client = r#await!(ClientBase::new(url))?;
+
+        r#await!(client.send(client::Login {
+            flag: "UN".to_owned(),
+            name: "test".to_owned(),
+            session: "none".to_owned(),
+            horizon_x: 3000,
+            horizon_y: 3000,
+            protocol: 5,
+        }))?;
+        r#await!(client.wait_for_reply(TIMEOUT, |p| match p {
+            ServerPacket::Login(_) => Some(()),
+            _ => None,
+        }))?;
+
+        Ok(client)
+    }
+
+    #[test]
+    fn login_and_move() {
+        crate::future::block_on(async {
+            let mut client = r#await!(connect(start_server())).unwrap();
+            let me = client.world().me.id;
+            assert!(client.world().players.contains_key(&me));
+
+            r#await!(client.press_key(KeyCode::Up)).unwrap();
+            r#await!(client.wait_for_reply(TIMEOUT, |p| match p {
+                ServerPacket::PlayerUpdate(p) if p.id.0 == me && p.keystate.up => Some(()),
+                _ => None,
+            }))
+            .unwrap();
+
+            assert!(client.world().get_me().keystate.up);
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "admin")]
+    fn admin_teleport() {
+        crate::future::block_on(async {
+            let mut client = r#await!(connect(start_server())).unwrap();
+            let me = client.world().me.id;
+            let pos = Position::new(1000.0, -500.0);
+
+            // The server sends our new position before the reply
+            r#await!(client.admin_teleport(me, pos)).unwrap();
+
+            let actual = client.world().get_me().pos;
+            assert!((actual - pos).length().inner() < 50.0);
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "admin")]
+    fn reconnect_after_kick() {
+        crate::future::block_on(async {
+            let addr = start_server();
+            let mut client = r#await!(connect(addr)).unwrap();
+            let mut admin = r#await!(connect(addr)).unwrap();
+            client.set_reconnect_policy(ReconnectPolicy {
+                initial_backoff: Duration::from_millis(10),
+                ..Default::default()
+            });
+
+            let old = client.world().me.id;
+            r#await!(client.press_key(KeyCode::Up)).unwrap();
+            r#await!(admin.admin_kick(old)).unwrap();
+
+            let mut events = vec![];
+            while let Some(evt) = r#await!(client.next()).unwrap() {
+                match evt {
+                    ClientEvent::Disconnected(_) => events.push("disconnected"),
+                    ClientEvent::Reconnected => {
+                        events.push("reconnected");
+                        break;
+                    }
+                    _ => (),
+                }
+            }
+            assert_eq!(events, vec!["disconnected", "reconnected"]);
+
+            // Logged in again as a new player with Up still held
+            let me = client.world().me.id;
+            assert_ne!(me, old);
+            assert!(client.world().keys.up);
+            r#await!(client.wait_for_reply(TIMEOUT, |p| match p {
+                ServerPacket::PlayerUpdate(p) if p.id.0 == me && p.keystate.up => Some(()),
+                _ => None,
+            }))
+            .unwrap();
+        });
+    }
+}
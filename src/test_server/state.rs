@@ -0,0 +1,587 @@
+use airmash_protocol::*;
+use futures::sync::mpsc::UnboundedSender;
+use hashbrown::HashMap;
+
+use std::time::{Duration, Instant};
+
+use crate::consts::CONFIG;
+use crate::game::{Mob, Player, World};
+
+/// Settings for the stand-in server.
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    /// The room name reported in the login packet.
+    pub room: String,
+    /// How often to send a `Ping` to every client.
+    pub ping_interval: Duration,
+    /// How often to send a `PlayerUpdate` for every
+    /// player, even if their keys haven't changed.
+    pub update_interval: Duration,
+    /// How often to launch a test missile across the
+    /// spawn area. `None` disables missiles.
+    pub missile_interval: Option<Duration>,
+    /// Where new players are spawned.
+    pub spawn: Position,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            room: "test".to_owned(),
+            ping_interval: Duration::from_secs(5),
+            update_interval: Duration::from_secs(1),
+            missile_interval: Some(Duration::from_secs(3)),
+            spawn: Position::new(0.0, 0.0),
+        }
+    }
+}
+
+struct Connection {
+    player: Player,
+    tx: UnboundedSender<ServerPacket>,
+    ping_sent: Option<(u32, Instant)>,
+}
+
+struct DroneMob {
+    mob: Mob,
+    despawn: Instant,
+}
+
+/// All the state shared between connections.
+pub(crate) struct ServerState {
+    config: ServerConfig,
+    connections: HashMap<u16, Connection>,
+    mobs: HashMap<u16, DroneMob>,
+    next_id: u16,
+    ping_num: u32,
+    start: Instant,
+    /// Set by the `game-state end` admin command to
+    /// freeze all movement.
+    paused: bool,
+
+    last_frame: Instant,
+    next_ping: Instant,
+    next_update: Instant,
+    next_missile: Instant,
+}
+
+impl ServerState {
+    pub fn new(config: ServerConfig) -> Self {
+        let now = Instant::now();
+
+        Self {
+            config,
+            connections: HashMap::default(),
+            mobs: HashMap::default(),
+            next_id: 1,
+            ping_num: 0,
+            start: now,
+            paused: false,
+
+            last_frame: now,
+            next_ping: now,
+            next_update: now,
+            next_missile: now,
+        }
+    }
+
+    fn clock(&self) -> u32 {
+        let elapsed = Instant::now() - self.start;
+        let millis = elapsed.as_secs() * 1000 + elapsed.subsec_millis() as u64;
+
+        // The client expects hundredths of a millisecond
+        (millis * 100) as u32
+    }
+
+    fn alloc_id(&mut self) -> u16 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1).max(1);
+        id
+    }
+
+    fn send_to(&self, id: u16, packet: ServerPacket) {
+        if let Some(conn) = self.connections.get(&id) {
+            // If this fails then the connection is already
+            // shutting down and will be removed shortly.
+            let _ = conn.tx.unbounded_send(packet);
+        }
+    }
+
+    fn broadcast(&self, packet: ServerPacket) {
+        for conn in self.connections.values() {
+            let _ = conn.tx.unbounded_send(packet.clone());
+        }
+    }
+
+    fn player_update(&self, player: &Player) -> ServerPacket {
+        server::PlayerUpdate {
+            clock: self.clock(),
+            id: player.id.into(),
+            keystate: player.keystate,
+            upgrades: player.upgrades,
+            pos: player.pos,
+            rot: player.rot,
+            speed: player.vel,
+        }
+        .into()
+    }
+}
+
+/// Packet handling
+impl ServerState {
+    /// Register a new connection and log it in. Returns
+    /// the id of the newly created player.
+    pub fn login(&mut self, login: client::Login, tx: UnboundedSender<ServerPacket>) -> u16 {
+        let id = self.alloc_id();
+        let player = Player {
+            id,
+            name: login.name,
+            flag: login.flag.parse().unwrap_or_default(),
+            team: Team(id),
+            plane: PlaneType::Predator,
+            status: PlayerStatus::Alive,
+            visible: true,
+            pos: self.config.spawn,
+            health: Health::new(1.0),
+            energy: Energy::new(1.0),
+            ..Default::default()
+        };
+
+        info!("Player {} logged in as {:?}", id, player.name);
+
+        self.broadcast(
+            server::PlayerNew {
+                id: id.into(),
+                status: player.status,
+                name: player.name.clone(),
+                ty: player.plane,
+                team: player.team,
+                pos: player.pos,
+                rot: player.rot,
+                flag: player.flag,
+                upgrades: player.upgrades,
+            }
+            .into(),
+        );
+
+        self.connections.insert(
+            id,
+            Connection {
+                player,
+                tx,
+                ping_sent: None,
+            },
+        );
+
+        let players = self
+            .connections
+            .values()
+            .map(|conn| &conn.player)
+            .map(|p| server::LoginPlayer {
+                id: p.id.into(),
+                status: p.status,
+                level: Level(0),
+                name: p.name.clone(),
+                ty: p.plane,
+                team: p.team,
+                pos: p.pos,
+                rot: p.rot,
+                flag: p.flag,
+                upgrades: p.upgrades,
+            })
+            .collect();
+
+        self.send_to(
+            id,
+            server::Login {
+                success: true,
+                id: id.into(),
+                team: Team(id),
+                clock: self.clock(),
+                token: "none".to_owned(),
+                ty: GameType::FFA,
+                room: self.config.room.clone(),
+                players,
+            }
+            .into(),
+        );
+
+        id
+    }
+
+    pub fn logout(&mut self, id: u16) {
+        if self.connections.remove(&id).is_some() {
+            info!("Player {} left", id);
+            self.broadcast(server::PlayerLeave { id: id.into() }.into());
+        }
+    }
+
+    pub fn handle_packet(&mut self, id: u16, packet: ClientPacket) {
+        use self::ClientPacket::*;
+
+        match packet {
+            Key(p) => self.handle_key(id, p),
+            Pong(p) => self.handle_pong(id, p),
+            Chat(p) => self.broadcast(
+                server::ChatPublic {
+                    id: id.into(),
+                    text: p.text,
+                }
+                .into(),
+            ),
+            Say(p) => self.broadcast(
+                server::ChatSay {
+                    id: id.into(),
+                    text: p.text,
+                }
+                .into(),
+            ),
+            #[cfg(feature = "admin")]
+            Command(p) => self.handle_command(id, p),
+            // Everything else is ignored by the stand-in server
+            _ => (),
+        }
+    }
+
+    fn handle_key(&mut self, id: u16, packet: client::Key) {
+        use self::KeyCode::*;
+
+        let update = match self.connections.get_mut(&id) {
+            Some(conn) => {
+                let player = &mut conn.player;
+                let ref mut keystate = player.keystate;
+
+                match packet.key {
+                    Up => keystate.up = packet.state,
+                    Down => keystate.down = packet.state,
+                    Left => keystate.left = packet.state,
+                    Right => keystate.right = packet.state,
+                    Special => match player.plane {
+                        PlaneType::Predator => keystate.boost = packet.state,
+                        PlaneType::Mohawk => keystate.strafe = packet.state,
+                        _ => (),
+                    },
+                    Fire => (),
+                }
+
+                player.clone()
+            }
+            None => return,
+        };
+
+        let packet = self.player_update(&update);
+        self.broadcast(packet);
+    }
+
+    fn handle_pong(&mut self, id: u16, packet: client::Pong) {
+        let players = self.connections.len() as u32;
+
+        if let Some(conn) = self.connections.get_mut(&id) {
+            if let Some((num, sent)) = conn.ping_sent.take() {
+                if num != packet.num {
+                    return;
+                }
+
+                let elapsed = Instant::now() - sent;
+                let ping = elapsed.as_secs() * 1000 + elapsed.subsec_millis() as u64;
+
+                let _ = conn.tx.unbounded_send(
+                    server::PingResult {
+                        ping: ping.min(u16::max_value() as u64) as u16,
+                        players_total: players,
+                        players_game: players,
+                    }
+                    .into(),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(feature = "admin")]
+fn expect_args(args: &[&str], count: usize) -> Result<(), String> {
+    if args.len() != count {
+        return Err(format!("expected {} arguments, got {}", count, args.len()));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "admin")]
+fn parse_arg<A: std::str::FromStr>(arg: &str) -> Result<A, String> {
+    arg.parse().map_err(|_| format!("invalid argument {:?}", arg))
+}
+
+/// Admin commands
+///
+/// These are the commands that the client's `admin_*`
+/// methods send. Each one is answered with a
+/// `CommandReply` of `<command>: <message>`, or
+/// `<command>: error: <reason>` if it failed.
+#[cfg(feature = "admin")]
+impl ServerState {
+    fn handle_command(&mut self, id: u16, packet: client::Command) {
+        let args: Vec<&str> = packet.data.split_whitespace().collect();
+        let result = match packet.com.as_str() {
+            "teleport" => self.admin_teleport(&args),
+            "team" => self.admin_team(&args),
+            "spawn-upgrade" => self.admin_spawn_upgrade(&args),
+            "kick" => self.admin_kick(&args),
+            "game-state" => self.admin_game_state(&args),
+            _ => {
+                let error = ErrorType::UnknownCommand;
+                self.send_to(id, server::Error { error }.into());
+                return;
+            }
+        };
+
+        let text = match result {
+            Ok(msg) => format!("{}: {}", packet.com, msg),
+            Err(reason) => format!("{}: error: {}", packet.com, reason),
+        };
+
+        self.send_to(
+            id,
+            server::CommandReply {
+                ty: CommandReplyType::ShowInConsole,
+                text,
+            }
+            .into(),
+        );
+    }
+
+    fn admin_player(&mut self, arg: &str) -> Result<&mut Player, String> {
+        let id: u16 = parse_arg(arg)?;
+
+        match self.connections.get_mut(&id) {
+            Some(conn) => Ok(&mut conn.player),
+            None => Err(format!("no player with id {}", id)),
+        }
+    }
+
+    fn admin_teleport(&mut self, args: &[&str]) -> Result<String, String> {
+        expect_args(args, 3)?;
+        let pos = Position::new(parse_arg::<f32>(args[1])?, parse_arg::<f32>(args[2])?);
+        let player = self.admin_player(args[0])?;
+
+        player.pos = pos;
+        player.vel = Velocity::default();
+        let player = player.clone();
+
+        let packet = self.player_update(&player);
+        self.broadcast(packet);
+
+        Ok(format!("moved {} to {} {}", player.id, args[1], args[2]))
+    }
+
+    fn admin_team(&mut self, args: &[&str]) -> Result<String, String> {
+        expect_args(args, 2)?;
+        let team = Team(parse_arg(args[1])?);
+        let player = self.admin_player(args[0])?;
+
+        player.team = team;
+        let id = player.id;
+
+        self.broadcast(
+            server::PlayerReteam {
+                players: vec![server::PlayerReteamPlayer {
+                    id: id.into(),
+                    team,
+                }],
+            }
+            .into(),
+        );
+
+        Ok(format!("moved {} to team {}", id, team.0))
+    }
+
+    fn admin_spawn_upgrade(&mut self, args: &[&str]) -> Result<String, String> {
+        expect_args(args, 3)?;
+        let ty = match parse_arg::<u8>(args[0])? {
+            4 => MobType::Upgrade,
+            8 => MobType::Shield,
+            9 => MobType::Inferno,
+            ty => return Err(format!("mob type {} is not a powerup", ty)),
+        };
+        let pos = Position::new(parse_arg::<f32>(args[1])?, parse_arg::<f32>(args[2])?);
+
+        let id = self.alloc_id();
+        self.broadcast(
+            server::MobUpdateStationary {
+                id: id.into(),
+                ty,
+                pos,
+            }
+            .into(),
+        );
+
+        Ok(format!("spawned {:?} {}", ty, id))
+    }
+
+    fn admin_kick(&mut self, args: &[&str]) -> Result<String, String> {
+        expect_args(args, 1)?;
+        let id: u16 = parse_arg(args[0])?;
+
+        if !self.connections.contains_key(&id) {
+            return Err(format!("no player with id {}", id));
+        }
+
+        let error = ErrorType::Kicked;
+        self.send_to(id, server::Error { error }.into());
+        // Dropping the connection closes the socket once
+        // everything queued for it has been sent.
+        self.logout(id);
+
+        Ok(format!("kicked {}", id))
+    }
+
+    fn admin_game_state(&mut self, args: &[&str]) -> Result<String, String> {
+        match args {
+            ["start"] => self.paused = false,
+            ["end"] => self.paused = true,
+            ["reset"] => {
+                let spawn = self.config.spawn;
+                let mut packets = vec![];
+
+                for conn in self.connections.values_mut() {
+                    let player = &mut conn.player;
+
+                    player.pos = spawn;
+                    player.vel = Velocity::default();
+                    player.health = Health::new(1.0);
+                    player.energy = Energy::new(1.0);
+
+                    packets.push(server::PlayerRespawn {
+                        id: player.id.into(),
+                        pos: player.pos,
+                        rot: player.rot,
+                        upgrades: player.upgrades,
+                    });
+                }
+
+                for packet in packets {
+                    self.broadcast(packet.into());
+                }
+            }
+            _ => return Err("expected one of start, end or reset".to_owned()),
+        }
+
+        Ok(format!("game state is now {}", args[0]))
+    }
+}
+
+/// Frame updates
+impl ServerState {
+    pub fn update(&mut self, now: Instant) {
+        let delta = (now - self.last_frame).into();
+        self.last_frame = now;
+
+        if !self.paused {
+            for conn in self.connections.values_mut() {
+                World::update_player(&mut conn.player, delta);
+            }
+            for drone in self.mobs.values_mut() {
+                if !World::update_mob(&mut drone.mob, delta) {
+                    drone.despawn = now;
+                }
+            }
+        }
+
+        if now >= self.next_ping {
+            self.next_ping = now + self.config.ping_interval;
+            self.send_pings(now);
+        }
+
+        if now >= self.next_update {
+            self.next_update = now + self.config.update_interval;
+
+            let updates: Vec<_> = self
+                .connections
+                .values()
+                .map(|conn| self.player_update(&conn.player))
+                .collect();
+
+            for packet in updates {
+                self.broadcast(packet);
+            }
+        }
+
+        if let Some(interval) = self.config.missile_interval {
+            if now >= self.next_missile && !self.paused {
+                self.next_missile = now + interval;
+                self.spawn_missile(now);
+            }
+        }
+
+        let expired: Vec<_> = self
+            .mobs
+            .values()
+            .filter(|drone| drone.despawn <= now)
+            .map(|drone| drone.mob)
+            .collect();
+
+        for mob in expired {
+            self.mobs.remove(&mob.id);
+            self.broadcast(
+                server::MobDespawn {
+                    id: mob.id.into(),
+                    ty: DespawnType::LifetimeEnded,
+                }
+                .into(),
+            );
+        }
+    }
+
+    fn send_pings(&mut self, now: Instant) {
+        let num = self.ping_num;
+        let clock = self.clock();
+        self.ping_num = self.ping_num.wrapping_add(1);
+
+        for conn in self.connections.values_mut() {
+            conn.ping_sent = Some((num, now));
+            let _ = conn.tx.unbounded_send(server::Ping { clock, num }.into());
+        }
+    }
+
+    /// Launch a missile that flies past the spawn point
+    /// so that bots have something to react to.
+    fn spawn_missile(&mut self, now: Instant) {
+        let ty = MobType::PredatorMissile;
+        let info = CONFIG.mobs[ty].missile.expect("Missile had no missile info");
+
+        let mob = Mob {
+            id: self.alloc_id(),
+            ty,
+            owner: None,
+            pos: self.config.spawn - Position::new(info.distance.inner() / 2.0, 200.0),
+            vel: Velocity::new(info.base_speed.inner(), 0.0),
+            accel: Accel::new(info.accel.inner(), 0.0),
+            max_speed: info.max_speed,
+        };
+
+        // Approximate how long the missile takes to reach its
+        // maximum range, assuming it's always at max speed.
+        let lifetime: Duration = Time::new(info.distance.inner() / info.max_speed.inner()).into();
+
+        self.broadcast(
+            server::MobUpdate {
+                clock: self.clock(),
+                id: mob.id.into(),
+                ty: mob.ty,
+                pos: mob.pos,
+                speed: mob.vel,
+                accel: mob.accel,
+                max_speed: mob.max_speed,
+            }
+            .into(),
+        );
+
+        self.mobs.insert(
+            mob.id,
+            DroneMob {
+                mob,
+                despawn: now + lifetime,
+            },
+        );
+    }
+}